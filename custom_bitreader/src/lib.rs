@@ -1,4 +1,7 @@
-use std::io::{Read, Seek, SeekFrom};
+use std::{
+    fmt,
+    io::{self, ErrorKind, Read, Seek, SeekFrom},
+};
 
 const SMALL_BUFFER: usize = 512;
 const LARGE_BUFFER: usize = 1024 * 128;
@@ -31,6 +34,79 @@ impl Stack {
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+pub struct ChunkBoundaryError {
+    pub expected: usize,
+    pub actual: usize,
+}
+
+impl fmt::Display for ChunkBoundaryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "chunk boundary mismatch, expected position {} got {}",
+            self.expected, self.actual
+        )
+    }
+}
+
+impl std::error::Error for ChunkBoundaryError {}
+
+// Adapts a plain `Read` source (a pipe, a decompressor) to the `Seek` bound of `BitReader`.
+// Only forward seeks are supported, which is all `BitReader::skip` ever does; they are served
+// by reading and discarding. The end of the stream is not known up front.
+pub struct ForwardReader<R> {
+    inner: R,
+    position: u64,
+}
+
+impl<R: Read> ForwardReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self { inner, position: 0 }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Read for ForwardReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: Read> Seek for ForwardReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(n) => Some(n),
+            SeekFrom::Current(n) => self.position.checked_add_signed(n),
+            SeekFrom::End(_) => None,
+        };
+
+        match target {
+            Some(target) if target >= self.position => {
+                let skipped = io::copy(
+                    &mut (&mut self.inner).take(target - self.position),
+                    &mut io::sink(),
+                )?;
+                self.position += skipped;
+                Ok(self.position)
+            }
+            _ => Err(io::Error::new(
+                ErrorKind::Unsupported,
+                "ForwardReader can only seek forward",
+            )),
+        }
+    }
+
+    fn stream_position(&mut self) -> io::Result<u64> {
+        Ok(self.position)
+    }
+}
+
 const MIN_STRING_BUFFER_LENGTH: usize = 256;
 const VALVE_MAX_STRING_LENGTH: usize = 4096;
 
@@ -45,6 +121,12 @@ where
     pub lazy_position: usize,
     pub chunk_targets: Stack,
     pub end_reached: bool,
+    pub data_end: Option<usize>,
+    // Set once a skip was cut short by the end of the underlying data.
+    pub skipped_past_end: bool,
+    // The first error the underlying data returned. The data is treated as ending there, so
+    // callers should check it before taking a short read for the end of the demo.
+    pub error: Option<io::Error>,
 }
 
 impl<T> BitReader<T>
@@ -64,7 +146,13 @@ where
     }
 
     pub fn open_with_buffer(mut underlying: T, mut buffer: Vec<u8>) -> Self {
-        underlying.read_exact(&mut buffer).unwrap();
+        let mut data_end = stream_end(&mut underlying);
+
+        let mut error = None;
+        let bytes = fill(&mut underlying, &mut buffer, &mut error);
+        if bytes < buffer.len() {
+            data_end.get_or_insert(bytes << 3);
+        }
         let bits_in_buffer: usize = if ((buffer.len() as isize) << 3) - (SLED_BITS as isize) < 0 {
             (((buffer.len() as isize) << 3) + (SLED_BITS as isize)) as usize
         } else {
@@ -78,6 +166,9 @@ where
             lazy_position: 0,
             chunk_targets: Stack(Vec::with_capacity(2)),
             end_reached: false,
+            data_end,
+            skipped_past_end: false,
+            error,
         }
     }
 
@@ -89,6 +180,25 @@ where
         self.lazy_position + self.offset
     }
 
+    // Bits left before the end of the underlying data, if the end is known yet.
+    pub fn remaining_bits(&self) -> Option<usize> {
+        self.data_end
+            .map(|end| end.saturating_sub(self.actual_position()))
+    }
+
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+
+    // True once a read went past the end of the underlying data, i.e. whatever was
+    // returned since then is garbage.
+    pub fn overrun(&self) -> bool {
+        self.skipped_past_end
+            || self
+                .data_end
+                .is_some_and(|end| self.actual_position() > end)
+    }
+
     fn advance(&mut self, bits: usize) {
         self.offset += bits;
         while self.offset > self.bits_in_buffer {
@@ -150,9 +260,7 @@ where
             Some(v) => v,
             None => n,
         };
-        std::str::from_utf8(&b[..end])
-            .expect("should have converted bytes to &str")
-            .to_string()
+        String::from_utf8_lossy(&b[..end]).into_owned()
     }
 
     pub fn read_signed_int(&mut self, n: usize) -> isize {
@@ -166,26 +274,23 @@ where
         self.chunk_targets.push(self.actual_position() + n);
     }
 
-    pub fn end_chunk(&mut self) {
+    pub fn end_chunk(&mut self) -> Result<(), ChunkBoundaryError> {
         let (new_stack, target) = self.chunk_targets.pop();
         self.chunk_targets.0 = new_stack;
         let delta = target as isize - self.actual_position() as isize;
 
-        match delta.cmp(&0) {
-            std::cmp::Ordering::Greater => {
-                self.skip(delta as usize);
-            }
-            std::cmp::Ordering::Less => panic!("Someone read beyond a chunk boundary, what a dick"),
-            std::cmp::Ordering::Equal => {}
+        if delta > 0 {
+            self.skip(delta as usize);
         }
 
         if target != self.actual_position() {
-            panic!(
-                "Skipping data failed, expected position {} got {}",
-                target,
-                self.actual_position()
-            );
+            return Err(ChunkBoundaryError {
+                expected: target,
+                actual: self.actual_position(),
+            });
         }
+
+        Ok(())
     }
 
     pub fn chunk_finished(&mut self) -> bool {
@@ -196,20 +301,29 @@ where
         let buffer_bits = self.bits_in_buffer as isize - self.offset as isize;
         if n as isize > buffer_bits + SLED_BITS as isize {
             let unbuffered_skip_bits = n - buffer_bits as usize;
-            let global_offset = self.underlying.stream_position().unwrap()
-                + ((unbuffered_skip_bits >> 3) - SLED) as u64;
-
-            self.lazy_position = (global_offset << 3) as usize;
-
-            self.underlying
-                .seek(SeekFrom::Start(global_offset))
-                .unwrap();
-
-            let bytes = self.underlying.read(&mut self.buffer).unwrap();
+            let global_offset = match self.underlying.stream_position() {
+                Ok(position) => position + ((unbuffered_skip_bits >> 3) - SLED) as u64,
+                Err(e) => return self.end_skip(e),
+            };
+
+            // Sources that can't seek past their end stop short of the target.
+            let reached = match self.underlying.seek(SeekFrom::Start(global_offset)) {
+                Ok(reached) => reached,
+                Err(e) => return self.end_skip(e),
+            };
+            self.skipped_past_end |= reached < global_offset;
+
+            self.lazy_position = (reached << 3) as usize;
+
+            let bytes = fill(&mut self.underlying, &mut self.buffer, &mut self.error);
+            if bytes < self.buffer.len() {
+                self.data_end
+                    .get_or_insert(self.lazy_position + (bytes << 3));
+            }
 
             self.offset = unbuffered_skip_bits & SLED_MASK;
 
-            self.bits_in_buffer = (bytes << 3) - SLED_BITS;
+            self.bits_in_buffer = (bytes << 3).saturating_sub(SLED_BITS);
             if bytes <= SLED {
                 self.bits_in_buffer += SLED_BITS;
             }
@@ -218,6 +332,45 @@ where
         }
     }
 
+    // The underlying data failed us mid skip: it ends where the skip started.
+    fn end_skip(&mut self, error: io::Error) {
+        self.error.get_or_insert(error);
+        let position = self.actual_position();
+        self.data_end = Some(self.data_end.map_or(position, |end| end.min(position)));
+        self.skipped_past_end = true;
+        self.end_reached = true;
+    }
+
+    // Moves to an absolute byte offset in the underlying data. Going backwards needs a
+    // seekable source; going forwards works on any of them.
+    pub fn seek_to(&mut self, byte: usize) -> io::Result<()> {
+        let target = byte << 3;
+        let position = self.actual_position();
+        if target >= position {
+            self.skip(target - position);
+            return Ok(());
+        }
+
+        self.underlying.seek(SeekFrom::Start(byte as u64))?;
+
+        let bytes = fill(&mut self.underlying, &mut self.buffer, &mut self.error);
+        if bytes < self.buffer.len() {
+            self.data_end.get_or_insert(target + (bytes << 3));
+        }
+
+        self.lazy_position = target;
+        self.offset = 0;
+        self.chunk_targets.0.clear();
+        self.skipped_past_end = false;
+
+        self.bits_in_buffer = (bytes << 3).saturating_sub(SLED_BITS);
+        if bytes <= SLED {
+            self.bits_in_buffer += SLED_BITS;
+        }
+
+        Ok(())
+    }
+
     fn refill_buffer(&mut self) {
         let src =
             &self.buffer[self.bits_in_buffer >> 3..(self.bits_in_buffer >> 3) + SLED].to_vec();
@@ -227,7 +380,15 @@ where
         self.offset -= self.bits_in_buffer;
         self.lazy_position += self.bits_in_buffer;
 
-        let bytes = self.underlying.read(&mut self.buffer[SLED..]).unwrap();
+        let bytes = fill(
+            &mut self.underlying,
+            &mut self.buffer[SLED..],
+            &mut self.error,
+        );
+        if bytes < self.buffer.len() - SLED {
+            self.data_end
+                .get_or_insert(self.lazy_position + ((SLED + bytes) << 3));
+        }
         self.bits_in_buffer = bytes << 3;
 
         if self.bits_in_buffer == 0 {
@@ -249,7 +410,7 @@ where
             }
             result.push(b);
         }
-        String::from_utf8(result).unwrap()
+        String::from_utf8_lossy(&result).into_owned()
     }
 
     pub fn read_float(&mut self) -> f32 {
@@ -405,6 +566,31 @@ where
     }
 }
 
+// Reads until `buf` is full or the underlying data is exhausted, so a short count means
+// the end was hit or, if `error` got set, that reading failed.
+fn fill<T: Read>(underlying: &mut T, buf: &mut [u8], error: &mut Option<io::Error>) -> usize {
+    let mut n = 0;
+    while n < buf.len() {
+        match underlying.read(&mut buf[n..]) {
+            Ok(0) => break,
+            Ok(read) => n += read,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => {
+                error.get_or_insert(e);
+                break;
+            }
+        }
+    }
+    n
+}
+
+fn stream_end<T: Seek>(underlying: &mut T) -> Option<usize> {
+    let current = underlying.stream_position().ok()?;
+    let end = underlying.seek(SeekFrom::End(0)).ok()?;
+    underlying.seek(SeekFrom::Start(current)).ok()?;
+    Some((end as usize) << 3)
+}
+
 fn uint64(b: &[u8]) -> u64 {
     u64::from_le_bytes(b[..8].try_into().unwrap())
 }
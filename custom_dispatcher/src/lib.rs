@@ -1,27 +1,90 @@
-use std::{collections::HashMap, pin::Pin, sync::Arc};
+use std::{collections::HashMap, sync::Arc};
 
+#[cfg(feature = "async")]
+use std::{future::Future, pin::Pin};
+
+use bincode::Options;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-type Callback = Arc<
-    dyn Fn(
-            Vec<u8>,
-        )
-            -> Pin<Box<(dyn futures::Future<Output = ()> + std::marker::Send + std::marker::Sync)>>
-        + 'static
-        + std::marker::Send
-        + std::marker::Sync,
->;
+#[cfg(feature = "async")]
+pub type ListenerFuture = Pin<Box<dyn Future<Output = ()> + Send + Sync>>;
+
+type SyncCallback = Arc<dyn Fn(String, Vec<u8>) + Send + Sync>;
+#[cfg(feature = "async")]
+type AsyncCallback = Arc<dyn Fn(String, Vec<u8>) -> ListenerFuture + Send + Sync>;
+
+#[derive(Clone)]
+enum Callback {
+    Sync(SyncCallback),
+    #[cfg(feature = "async")]
+    Async(AsyncCallback),
+}
 
 pub struct Listener {
     callback: Callback,
+    // Calls left before the listener is removed, or None for no limit.
     limit: Option<u64>,
-    _id: String,
+    // Registration order across all events, so wildcard and exact listeners interleave
+    // the way they were added.
+    order: u64,
+    id: String,
+}
+
+// Finished spawned handlers are dropped from `pending` whenever it grows past this.
+#[cfg(feature = "tokio")]
+const PENDING_PRUNE_THRESHOLD: usize = 1024;
+
+// How async listeners are run. Sync listeners are always called right away, in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Delivery {
+    // With the `tokio` feature, and a runtime to spawn on, every async listener runs on its
+    // own task and `emit` returns before they finish. Otherwise they wait in `pending` until
+    // `flush`.
+    #[default]
+    Spawned,
+    // Async listeners are awaited one after the other, in registration order, by
+    // `emit_async` before it returns.
+    Ordered,
+}
+
+#[cfg(feature = "async")]
+enum Pending {
+    Queued(ListenerFuture),
+    #[cfg(feature = "tokio")]
+    Spawned(tokio::task::JoinHandle<()>),
 }
 
 #[derive(Default)]
 pub struct EventEmitter {
     pub listeners: HashMap<String, Vec<Listener>>,
+    pub delivery: Delivery,
+    #[cfg(feature = "async")]
+    pending: Vec<Pending>,
+    // Keys of `listeners` ending in `*`, which match every event starting with what comes
+    // before it.
+    wildcards: Vec<String>,
+    next_order: u64,
+}
+
+fn matches(pattern: &str, event: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => event.starts_with(prefix),
+        None => pattern == event,
+    }
+}
+
+// Decodes a payload for a typed listener. Rejects trailing bytes so a wildcard listener
+// is only called for events whose payload really is a `T`; the rest are skipped.
+fn decode<T>(bytes: &[u8]) -> Option<T>
+where
+    for<'de> T: Deserialize<'de>,
+{
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .reject_trailing_bytes()
+        .deserialize(bytes)
+        .ok()
 }
 
 impl EventEmitter {
@@ -29,62 +92,201 @@ impl EventEmitter {
         Self { ..Self::default() }
     }
 
-    pub async fn on<FN, T>(&mut self, event: &str, callback: FN) -> String
+    pub fn with_delivery(delivery: Delivery) -> Self {
+        Self {
+            delivery,
+            ..Self::default()
+        }
+    }
+
+    pub fn on<FN, T>(&mut self, event: &str, callback: FN) -> String
+    where
+        for<'de> T: Deserialize<'de>,
+        FN: Fn(T) + 'static + Send + Sync,
+    {
+        self.on_limited(event, None, callback)
+    }
+
+    // Same as `on`, removed after its first call.
+    pub fn once<FN, T>(&mut self, event: &str, callback: FN) -> String
+    where
+        for<'de> T: Deserialize<'de>,
+        FN: Fn(T) + 'static + Send + Sync,
+    {
+        self.on_limited(event, Some(1), callback)
+    }
+
+    pub fn on_limited<FN, T>(&mut self, event: &str, limit: Option<u64>, callback: FN) -> String
     where
         for<'de> T: Deserialize<'de>,
-        FN: Fn(
-                T,
-            ) -> Pin<
-                Box<(dyn futures::Future<Output = ()> + std::marker::Send + std::marker::Sync)>,
-            >
-            + 'static
-            + std::marker::Send
-            + std::marker::Sync,
+        FN: Fn(T) + 'static + Send + Sync,
     {
-        self.on_limited(event, None, callback).await
+        let parsed_callback = move |_: String, bytes: Vec<u8>| {
+            if let Some(value) = decode::<T>(&bytes) {
+                callback(value)
+            }
+        };
+
+        self.add_listener(event, limit, Callback::Sync(Arc::new(parsed_callback)))
     }
 
-    pub async fn emit<T>(&mut self, event: &str, value: T) -> Vec<tokio::task::JoinHandle<()>>
+    // Gets the event name along with the still bincode encoded payload, for wildcard
+    // subscriptions that see events of different types. Typed listeners on such a
+    // pattern skip the events whose payload doesn't decode as their type.
+    pub fn on_raw<FN>(&mut self, event: &str, callback: FN) -> String
+    where
+        FN: Fn(String, Vec<u8>) + 'static + Send + Sync,
+    {
+        self.add_listener(event, None, Callback::Sync(Arc::new(callback)))
+    }
+
+    // Removes the listener `on` and friends returned `id` for. Returns false if it was
+    // already gone.
+    pub fn off(&mut self, id: &str) -> bool {
+        let found = self.listeners.iter_mut().find_map(|(event, listeners)| {
+            let index = listeners.iter().position(|listener| listener.id == id)?;
+            listeners.remove(index);
+            Some(event.to_owned())
+        });
+
+        match found {
+            Some(event) => {
+                self.remove_if_empty(&event);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn has_listeners(&self, event: &str) -> bool {
+        self.listeners.contains_key(event)
+            || self.wildcards.iter().any(|pattern| matches(pattern, event))
+    }
+
+    // Calls the sync listeners of `event` before returning. Async ones are handed over
+    // according to `delivery`; with `Delivery::Ordered` they are driven to completion on the
+    // calling thread, so this must not be called from async code. Use `emit_async` there.
+    pub fn emit<T>(&mut self, event: &str, value: T)
     where
         T: Serialize,
     {
-        let mut callback_handlers: Vec<tokio::task::JoinHandle<()>> = Vec::new();
+        self.deliver(event, value);
 
-        if let Some(listeners) = self.listeners.get_mut(event) {
-            let bytes: Vec<u8> = bincode::serialize(&value).unwrap();
+        #[cfg(feature = "async")]
+        if self.delivery == Delivery::Ordered && !self.pending.is_empty() {
+            futures::executor::block_on(self.flush());
+        }
+    }
+
+    fn deliver<T>(&mut self, event: &str, value: T)
+    where
+        T: Serialize,
+    {
+        if !self.has_listeners(event) {
+            return;
+        }
 
-            let mut listeners_to_remove: Vec<usize> = Vec::new();
-            for (index, listener) in listeners.iter_mut().enumerate() {
-                let cloned_bytes = bytes.clone();
-                let callback = Arc::clone(&listener.callback);
+        let mut keys = vec![event.to_owned()];
+        keys.extend(
+            self.wildcards
+                .iter()
+                .filter(|pattern| matches(pattern, event))
+                .cloned(),
+        );
 
-                match listener.limit {
-                    None => {
-                        callback_handlers.push(tokio::spawn(Box::pin(async move {
-                            callback(cloned_bytes).await;
-                        })));
+        let mut due: Vec<(u64, Callback)> = Vec::new();
+        for key in &keys {
+            if let Some(listeners) = self.listeners.get_mut(key) {
+                listeners.retain_mut(|listener| {
+                    if listener.limit == Some(0) {
+                        return false;
                     }
-                    Some(limit) => {
-                        if limit != 0 {
-                            callback_handlers.push(tokio::spawn(Box::pin(async move {
-                                callback(cloned_bytes).await;
-                            })));
-                        } else {
-                            listeners_to_remove.push(index);
+
+                    due.push((listener.order, listener.callback.clone()));
+                    match &mut listener.limit {
+                        Some(limit) => {
+                            *limit -= 1;
+                            *limit > 0
                         }
+                        None => true,
                     }
+                });
+            }
+            self.remove_if_empty(key);
+        }
+
+        if due.is_empty() {
+            return;
+        }
+        if keys.len() > 1 {
+            due.sort_by_key(|(order, _)| *order);
+        }
+
+        let bytes: Vec<u8> = bincode::serialize(&value).unwrap();
+        for (_, callback) in due {
+            match callback {
+                Callback::Sync(callback) => callback(event.to_owned(), bytes.clone()),
+                #[cfg(feature = "async")]
+                Callback::Async(callback) => {
+                    let future = callback(event.to_owned(), bytes.clone());
+                    self.hand_over(future);
                 }
             }
+        }
+    }
 
-            for index in listeners_to_remove.into_iter().rev() {
-                listeners.remove(index);
+    fn add_listener(&mut self, event: &str, limit: Option<u64>, callback: Callback) -> String {
+        let id = Uuid::new_v4().to_string();
+        let listener = Listener {
+            id: id.clone(),
+            limit,
+            order: self.next_order,
+            callback,
+        };
+        self.next_order += 1;
+
+        match self.listeners.get_mut(event) {
+            Some(callbacks) => {
+                callbacks.push(listener);
+            }
+            None => {
+                self.listeners.insert(event.to_string(), vec![listener]);
+                if event.ends_with('*') {
+                    self.wildcards.push(event.to_string());
+                }
             }
         }
 
-        callback_handlers
+        id
     }
 
-    pub async fn on_limited<FN, T>(
+    fn remove_if_empty(&mut self, event: &str) {
+        if self.listeners.get(event).is_some_and(Vec::is_empty) {
+            self.listeners.remove(event);
+            self.wildcards.retain(|pattern| pattern != event);
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl EventEmitter {
+    pub fn on_async<FN, T>(&mut self, event: &str, callback: FN) -> String
+    where
+        for<'de> T: Deserialize<'de>,
+        FN: Fn(T) -> ListenerFuture + 'static + Send + Sync,
+    {
+        self.on_limited_async(event, None, callback)
+    }
+
+    pub fn once_async<FN, T>(&mut self, event: &str, callback: FN) -> String
+    where
+        for<'de> T: Deserialize<'de>,
+        FN: Fn(T) -> ListenerFuture + 'static + Send + Sync,
+    {
+        self.on_limited_async(event, Some(1), callback)
+    }
+
+    pub fn on_limited_async<FN, T>(
         &mut self,
         event: &str,
         limit: Option<u64>,
@@ -92,36 +294,234 @@ impl EventEmitter {
     ) -> String
     where
         for<'de> T: Deserialize<'de>,
-        FN: Fn(
-                T,
-            ) -> Pin<
-                Box<(dyn futures::Future<Output = ()> + std::marker::Send + std::marker::Sync)>,
-            >
-            + 'static
-            + std::marker::Send
-            + std::marker::Sync,
+        FN: Fn(T) -> ListenerFuture + 'static + Send + Sync,
     {
-        let id = Uuid::new_v4().to_string();
-        let parsed_callback = move |bytes: Vec<u8>| {
-            let value: T = bincode::deserialize(&bytes).unwrap();
-            callback(value)
+        let parsed_callback = move |_: String, bytes: Vec<u8>| -> ListenerFuture {
+            match decode::<T>(&bytes) {
+                Some(value) => callback(value),
+                None => Box::pin(async {}),
+            }
         };
 
-        let listener = Listener {
-            _id: id.clone(),
-            limit,
-            callback: Arc::new(parsed_callback),
-        };
+        self.add_listener(event, limit, Callback::Async(Arc::new(parsed_callback)))
+    }
 
-        match self.listeners.get_mut(event) {
-            Some(callbacks) => {
-                callbacks.push(listener);
+    pub fn on_raw_async<FN>(&mut self, event: &str, callback: FN) -> String
+    where
+        FN: Fn(String, Vec<u8>) -> ListenerFuture + 'static + Send + Sync,
+    {
+        self.add_listener(event, None, Callback::Async(Arc::new(callback)))
+    }
+
+    // `emit`, then with `Delivery::Ordered` waits for the async listeners it started.
+    pub async fn emit_async<T>(&mut self, event: &str, value: T)
+    where
+        T: Serialize,
+    {
+        self.deliver(event, value);
+        if self.delivery == Delivery::Ordered {
+            self.flush().await;
+        }
+    }
+
+    // Waits for every async listener started so far to finish, in the order they were
+    // started.
+    pub async fn flush(&mut self) {
+        for pending in self.pending.drain(..) {
+            match pending {
+                Pending::Queued(future) => future.await,
+                #[cfg(feature = "tokio")]
+                Pending::Spawned(handle) => {
+                    let _ = handle.await;
+                }
             }
-            None => {
-                self.listeners.insert(event.to_string(), vec![listener]);
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    fn hand_over(&mut self, future: ListenerFuture) {
+        use futures::FutureExt;
+
+        match self.delivery {
+            Delivery::Ordered => self.pending.push(Pending::Queued(future)),
+            Delivery::Spawned => {
+                let pending = match tokio::runtime::Handle::try_current() {
+                    Ok(runtime) => Pending::Spawned(runtime.spawn(future)),
+                    Err(_) => Pending::Queued(future),
+                };
+                self.pending.push(pending);
+
+                if self.pending.len() >= PENDING_PRUNE_THRESHOLD {
+                    self.pending.retain_mut(|pending| match pending {
+                        Pending::Spawned(handle) => handle.now_or_never().is_none(),
+                        Pending::Queued(_) => true,
+                    });
+                }
             }
         }
+    }
 
-        id
+    #[cfg(not(feature = "tokio"))]
+    fn hand_over(&mut self, future: ListenerFuture) {
+        self.pending.push(Pending::Queued(future));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    type Log = Arc<Mutex<Vec<String>>>;
+
+    fn record(log: &Log, name: &'static str) -> impl Fn(i32) + Send + Sync + 'static {
+        let log = Arc::clone(log);
+        move |value: i32| log.lock().unwrap().push(format!("{name}:{value}"))
+    }
+
+    fn entries(log: &Log) -> Vec<String> {
+        log.lock().unwrap().drain(..).collect()
+    }
+
+    #[test]
+    fn off_removes_the_listener() {
+        let log = Log::default();
+        let mut emitter = EventEmitter::new();
+        let id = emitter.on("round_start", record(&log, "a"));
+
+        emitter.emit("round_start", 1);
+        assert!(emitter.off(&id));
+        assert!(!emitter.off(&id));
+        emitter.emit("round_start", 2);
+
+        assert_eq!(entries(&log), ["a:1"]);
+        assert!(!emitter.has_listeners("round_start"));
+    }
+
+    #[test]
+    fn once_fires_a_single_time() {
+        let log = Log::default();
+        let mut emitter = EventEmitter::new();
+        emitter.once("round_end", record(&log, "a"));
+
+        emitter.emit("round_end", 1);
+        emitter.emit("round_end", 2);
+
+        assert_eq!(entries(&log), ["a:1"]);
+        assert!(!emitter.has_listeners("round_end"));
+    }
+
+    #[test]
+    fn limit_counts_down() {
+        let log = Log::default();
+        let mut emitter = EventEmitter::new();
+        emitter.on_limited("player_death", Some(3), record(&log, "a"));
+
+        for value in 0..5 {
+            emitter.emit("player_death", value);
+        }
+
+        assert_eq!(entries(&log), ["a:0", "a:1", "a:2"]);
+        assert!(!emitter.has_listeners("player_death"));
+    }
+
+    #[test]
+    fn wildcards_match_by_prefix() {
+        let log = Log::default();
+        let mut emitter = EventEmitter::new();
+        emitter.on("*", record(&log, "all"));
+        emitter.on("grenade_*", record(&log, "grenade"));
+
+        emitter.emit("grenade_thrown", 1);
+        emitter.emit("grenade_bounce", 2);
+        emitter.emit("bomb_planted", 3);
+
+        assert_eq!(
+            entries(&log),
+            ["all:1", "grenade:1", "all:2", "grenade:2", "all:3"]
+        );
+        assert!(emitter.has_listeners("grenade_projectile_throw"));
+        assert!(emitter.has_listeners("anything"));
+    }
+
+    #[test]
+    fn typed_wildcard_listeners_skip_other_payload_types() {
+        let log = Log::default();
+        let mut emitter = EventEmitter::new();
+        emitter.on("round_*", record(&log, "typed"));
+        let names = Log::default();
+        let raw = Arc::clone(&names);
+        emitter.on_raw("round_*", move |event, _| raw.lock().unwrap().push(event));
+
+        emitter.emit("round_start", 1);
+        emitter.emit("round_end", "CT".to_string());
+        emitter.emit("round_freeze_end", 2_i64);
+        emitter.emit("round_officially_ended", 3);
+
+        assert_eq!(entries(&log), ["typed:1", "typed:3"]);
+        assert_eq!(
+            entries(&names),
+            [
+                "round_start",
+                "round_end",
+                "round_freeze_end",
+                "round_officially_ended"
+            ]
+        );
+    }
+
+    #[test]
+    fn listeners_run_in_registration_order() {
+        let log = Log::default();
+        let mut emitter = EventEmitter::new();
+        emitter.on("weapon_fire", record(&log, "first"));
+        emitter.on("weapon_*", record(&log, "second"));
+        emitter.on("weapon_fire", record(&log, "third"));
+
+        emitter.emit("weapon_fire", 7);
+
+        assert_eq!(entries(&log), ["first:7", "second:7", "third:7"]);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn spawned_delivery_outside_a_runtime_waits_for_flush() {
+        let log = Log::default();
+        let mut emitter = EventEmitter::new();
+        let listener_log = Arc::clone(&log);
+        emitter.on_async("round_start", move |value: i32| {
+            let log = Arc::clone(&listener_log);
+            Box::pin(async move { log.lock().unwrap().push(format!("a:{value}")) })
+        });
+
+        emitter.emit("round_start", 1);
+        assert!(entries(&log).is_empty());
+
+        futures::executor::block_on(emitter.flush());
+        assert_eq!(entries(&log), ["a:1"]);
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn ordered_delivery_awaits_async_listeners_in_order() {
+        let log = Log::default();
+        let mut emitter = EventEmitter::with_delivery(Delivery::Ordered);
+        for name in ["first", "second"] {
+            let log = Arc::clone(&log);
+            emitter.on_async("round_start", move |value: i32| {
+                let log = Arc::clone(&log);
+                Box::pin(async move { log.lock().unwrap().push(format!("{name}:{value}")) })
+            });
+        }
+        emitter.on("round_start", record(&log, "sync"));
+
+        emitter.emit("round_start", 1);
+        futures::executor::block_on(emitter.emit_async("round_start", 2));
+
+        assert_eq!(
+            entries(&log),
+            ["sync:1", "first:1", "second:1", "sync:2", "first:2", "second:2"]
+        );
     }
 }
@@ -0,0 +1,124 @@
+use std::{
+    io::{Read, Seek},
+    path::Path,
+};
+
+use crate::{DemoSource, Frame, ParseError, Parser};
+
+// Runs a `Parser` on tokio's blocking pool, so parsing doesn't hold up the runtime's worker
+// threads. Handlers and listeners are registered on the wrapped parser through `parser_mut`.
+pub struct AsyncParser<R = DemoSource>
+where
+    R: Read + Seek + Send + 'static,
+{
+    // Only None while a call is running on the blocking pool.
+    parser: Option<Parser<R>>,
+}
+
+impl AsyncParser<DemoSource> {
+    pub async fn new_from_file<P>(path: P) -> Result<Self, ParseError>
+    where
+        P: AsRef<Path> + Send + 'static,
+    {
+        let parser = blocking(move || Parser::new_from_file(path)).await?;
+        Ok(AsyncParser::new(parser))
+    }
+
+    pub async fn parse_many<P, F>(
+        paths: Vec<P>,
+        concurrency: usize,
+        setup: F,
+    ) -> Vec<Result<Self, ParseError>>
+    where
+        P: AsRef<Path> + Send + Sync + 'static,
+        F: FnMut(&mut Parser<DemoSource>) + Send + 'static,
+    {
+        blocking(move || Parser::parse_many(paths, concurrency, setup))
+            .await
+            .into_iter()
+            .map(|result| result.map(AsyncParser::new))
+            .collect()
+    }
+}
+
+impl<R> AsyncParser<R>
+where
+    R: Read + Seek + Send + 'static,
+{
+    pub fn new(parser: Parser<R>) -> Self {
+        AsyncParser {
+            parser: Some(parser),
+        }
+    }
+
+    pub fn parser(&self) -> &Parser<R> {
+        self.parser.as_ref().expect("AsyncParser is not in use")
+    }
+
+    pub fn parser_mut(&mut self) -> &mut Parser<R> {
+        self.parser.as_mut().expect("AsyncParser is not in use")
+    }
+
+    pub fn into_inner(self) -> Parser<R> {
+        self.parser.expect("AsyncParser is not in use")
+    }
+
+    // Also waits for the async listeners still running once the demo has been parsed.
+    pub async fn parse_to_end(&mut self) -> Result<(), ParseError> {
+        self.run(|parser| parser.parse_to_end()).await?;
+        self.flush().await;
+        Ok(())
+    }
+
+    pub async fn next_frame(&mut self) -> Result<Option<Frame>, ParseError> {
+        self.run(|parser| parser.next_frame()).await
+    }
+
+    pub async fn seek_to_tick(&mut self, tick: isize) -> Result<bool, ParseError> {
+        self.run(move |parser| parser.seek_to_tick(tick)).await
+    }
+
+    pub async fn seek_to_round(&mut self, round: i32) -> Result<bool, ParseError> {
+        self.run(move |parser| parser.seek_to_round(round)).await
+    }
+
+    // Waits for every async listener spawned so far to finish running.
+    pub async fn flush(&mut self) {
+        let parser = self.parser_mut();
+        parser.dispatcher.flush().await;
+
+        // The state's lock can't be held across an await, so its dispatcher is taken out
+        // while flushing. Nothing else can reach it in the meantime.
+        let state = std::sync::Arc::clone(&parser.state);
+        let mut dispatcher = std::mem::take(&mut *state.dispatcher.lock());
+        dispatcher.flush().await;
+        *state.dispatcher.lock() = dispatcher;
+    }
+
+    async fn run<T, F>(&mut self, f: F) -> T
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Parser<R>) -> T + Send + 'static,
+    {
+        let mut parser = self.parser.take().expect("AsyncParser is not in use");
+        let (parser, result) = blocking(move || {
+            let result = f(&mut parser);
+            (parser, result)
+        })
+        .await;
+
+        self.parser = Some(parser);
+        result
+    }
+}
+
+async fn blocking<T, F>(f: F) -> T
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    match tokio::task::spawn_blocking(f).await {
+        Ok(result) => result,
+        Err(e) => std::panic::resume_unwind(e.into_panic()),
+    }
+}
@@ -1,4 +1,20 @@
-#[derive(Debug)]
+use prost::Message;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    convar::CnetMsgSetConVar,
+    entity::CsvcMsgPacketEntities,
+    gameevent::{CsvcMsgGameEvent, CsvcMsgGameEventList},
+    serverinfo::CsvcMsgServerInfo,
+    sound::CsvcMsgSounds,
+    stringtable::{CsvcMsgCreateStringTable, CsvcMsgUpdateStringTable},
+    tempentity::CsvcMsgTempEntities,
+    tick::CnetMsgTick,
+    voice::{CsvcMsgVoiceData, CsvcMsgVoiceInit},
+    ParseError,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PacketCommand {
     Signon = 1,
     Packet = 2,
@@ -11,24 +27,26 @@ pub enum PacketCommand {
     String = 9,
 }
 
-impl From<u8> for PacketCommand {
-    fn from(cmd: u8) -> Self {
+impl TryFrom<u8> for PacketCommand {
+    type Error = u8;
+
+    fn try_from(cmd: u8) -> Result<Self, Self::Error> {
         match cmd {
-            1 => PacketCommand::Signon,
-            2 => PacketCommand::Packet,
-            3 => PacketCommand::Sync,
-            4 => PacketCommand::Console,
-            5 => PacketCommand::User,
-            6 => PacketCommand::Data,
-            7 => PacketCommand::Stop,
-            8 => PacketCommand::Custom,
-            9 => PacketCommand::String,
-            _ => panic!("cmd: {cmd}"),
+            1 => Ok(PacketCommand::Signon),
+            2 => Ok(PacketCommand::Packet),
+            3 => Ok(PacketCommand::Sync),
+            4 => Ok(PacketCommand::Console),
+            5 => Ok(PacketCommand::User),
+            6 => Ok(PacketCommand::Data),
+            7 => Ok(PacketCommand::Stop),
+            8 => Ok(PacketCommand::Custom),
+            9 => Ok(PacketCommand::String),
+            _ => Err(cmd),
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MessageType {
     Tick = 4,
     SignonState = 7,
@@ -82,16 +100,100 @@ impl MessageType {
     pub fn is_skippable(&self) -> bool {
         matches!(
             self,
-            MessageType::Tick
-                | MessageType::SignonState
+            MessageType::SignonState
                 | MessageType::ClassInfo
-                | MessageType::VoiceInit
-                | MessageType::VoiceData
-                | MessageType::Sounds
                 | MessageType::SetView
-                | MessageType::TempEntities
                 | MessageType::Prefetch
                 | MessageType::PlayerAvatarData
         )
     }
 }
+
+// The text of a dem_consolecmd frame, as emitted on the "console_command" channel.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsoleCommand {
+    pub tick: isize,
+    pub command: String,
+}
+
+// One demo frame, as handed out by `Parser::next_frame`.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub command: PacketCommand,
+    pub tick: isize,
+    pub messages: Vec<FrameMessage>,
+}
+
+// A net message of a Signon/Packet frame, decoded. Message types the parser skips without
+// reading are not included.
+#[derive(Debug, Clone)]
+pub enum FrameMessage {
+    Tick(CnetMsgTick),
+    SetConVar(CnetMsgSetConVar),
+    ServerInfo(CsvcMsgServerInfo),
+    CreateStringTable(CsvcMsgCreateStringTable),
+    UpdateStringTable(CsvcMsgUpdateStringTable),
+    GameEvent(CsvcMsgGameEvent),
+    GameEventList(CsvcMsgGameEventList),
+    PacketEntities(CsvcMsgPacketEntities),
+    TempEntities(CsvcMsgTempEntities),
+    VoiceInit(CsvcMsgVoiceInit),
+    VoiceData(CsvcMsgVoiceData),
+    Sounds(CsvcMsgSounds),
+    // A message id the parser doesn't know, still protobuf encoded.
+    Unknown { id: u32, data: Vec<u8> },
+}
+
+impl FrameMessage {
+    pub fn decode(id: u32, data: &[u8]) -> Result<Self, ParseError> {
+        macro_rules! decode {
+            ($variant:ident, $msg:ty) => {
+                <$msg>::decode(data)
+                    .map(FrameMessage::$variant)
+                    .map_err(|e| {
+                        ParseError::decode(
+                            concat!("Failed to decode bytes into ", stringify!($msg)),
+                            e,
+                        )
+                    })
+            };
+        }
+
+        match MessageType::from(id) {
+            MessageType::Tick => decode!(Tick, CnetMsgTick),
+            MessageType::SetConVar => decode!(SetConVar, CnetMsgSetConVar),
+            MessageType::ServerInfo => decode!(ServerInfo, CsvcMsgServerInfo),
+            MessageType::CreateStringTable => decode!(CreateStringTable, CsvcMsgCreateStringTable),
+            MessageType::UpdateStringTable => decode!(UpdateStringTable, CsvcMsgUpdateStringTable),
+            MessageType::GameEvent => decode!(GameEvent, CsvcMsgGameEvent),
+            MessageType::GameEventList => decode!(GameEventList, CsvcMsgGameEventList),
+            MessageType::PacketEntities => decode!(PacketEntities, CsvcMsgPacketEntities),
+            MessageType::TempEntities => decode!(TempEntities, CsvcMsgTempEntities),
+            MessageType::VoiceInit => decode!(VoiceInit, CsvcMsgVoiceInit),
+            MessageType::VoiceData => decode!(VoiceData, CsvcMsgVoiceData),
+            MessageType::Sounds => decode!(Sounds, CsvcMsgSounds),
+            _ => Ok(FrameMessage::Unknown {
+                id,
+                data: data.to_vec(),
+            }),
+        }
+    }
+
+    pub fn kind(&self) -> MessageType {
+        match self {
+            FrameMessage::Tick(_) => MessageType::Tick,
+            FrameMessage::SetConVar(_) => MessageType::SetConVar,
+            FrameMessage::ServerInfo(_) => MessageType::ServerInfo,
+            FrameMessage::CreateStringTable(_) => MessageType::CreateStringTable,
+            FrameMessage::UpdateStringTable(_) => MessageType::UpdateStringTable,
+            FrameMessage::GameEvent(_) => MessageType::GameEvent,
+            FrameMessage::GameEventList(_) => MessageType::GameEventList,
+            FrameMessage::PacketEntities(_) => MessageType::PacketEntities,
+            FrameMessage::TempEntities(_) => MessageType::TempEntities,
+            FrameMessage::VoiceInit(_) => MessageType::VoiceInit,
+            FrameMessage::VoiceData(_) => MessageType::VoiceData,
+            FrameMessage::Sounds(_) => MessageType::Sounds,
+            FrameMessage::Unknown { id, .. } => MessageType::from(*id),
+        }
+    }
+}
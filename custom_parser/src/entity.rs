@@ -4,8 +4,8 @@ use std::{
 };
 
 use crate::{
-    equipment::{_delete_thrown_grenade, EQ_DECOY, EQ_FLASH, EQ_INCENDIARY, EQ_MOLOTOV, EQ_SMOKE},
-    GLOBAL_DISPATCHER, GRENADE_MODEL_INDICES, GRENADE_PROJECTILES, PLAYERS_BY_ENTITY_ID,
+    equipment::{_delete_thrown_grenade, EQ_DECOY, EQ_INCENDIARY, EQ_MOLOTOV, EQ_SMOKE},
+    ParseError, ParserState,
 };
 use ahash::AHashMap as HashMap;
 use custom_bitreader::BitReader;
@@ -192,21 +192,17 @@ impl Entity {
     }
 
     #[inline]
-    pub async fn destroy(&mut self) {
+    pub fn destroy(&mut self, state: &ParserState) {
         if self._is_grenade() {
-            let deleted_proj = GRENADE_PROJECTILES.lock().await.remove(&self.id);
+            let deleted_proj = state.grenade_projectiles.lock().remove(&self.id);
             if let Some(proj) = deleted_proj {
-                if proj.weapon_instance.eq_type == EQ_FLASH {
-                    todo!();
-                }
-
                 let is_inferno = proj.weapon_instance.eq_type == EQ_MOLOTOV
                     || proj.weapon_instance.eq_type == EQ_INCENDIARY;
                 let is_smoke = proj.weapon_instance.eq_type == EQ_SMOKE;
                 let is_decoy = proj.weapon_instance.eq_type == EQ_DECOY;
 
                 if !is_inferno && !is_smoke && !is_decoy {
-                    _delete_thrown_grenade(&proj.thrower, proj.weapon_instance.eq_type).await;
+                    _delete_thrown_grenade(state, &proj.thrower, proj.weapon_instance.eq_type);
                 }
             }
         }
@@ -311,7 +307,8 @@ impl Entity {
 
     #[inline]
     pub fn _property_value_must(&self, name: &str) -> PropertyValueEnum {
-        self.property(name).unwrap().value
+        self.property(name)
+            .map_or(PropertyValueEnum::None, |prop| prop.value)
     }
 
     #[inline]
@@ -324,11 +321,12 @@ impl Entity {
     }
 
     #[inline]
-    pub async fn apply_update<T: Read + Seek + Send>(
+    pub fn apply_update<T: Read + Seek + Send>(
         &mut self,
+        state: &ParserState,
         reader: &mut BitReader<T>,
         prop_indices: &mut Vec<u32>,
-    ) {
+    ) -> Result<(), ParseError> {
         prop_indices.clear();
         let mut idx = -1_i32;
         let new_way = reader.read_bit();
@@ -339,28 +337,35 @@ impl Entity {
         }
         let is_blind = self.is_blind();
         let mut position_updated = false;
-        for idx in prop_indices {
-            decode_prop(&mut self.props[*idx as usize], reader);
-            match self.props[*idx as usize].entry.name.as_str() {
-                ENTITY_PLAYER_POSITION_XY_PROP | ENTITY_PLAYER_POSITION_Z_PROP => {
-                    if self.is_player() {
-                        position_updated = true;
-                    }
+        for idx in prop_indices.iter().map(|idx| *idx as usize) {
+            if idx >= self.props.len() {
+                return Err(ParseError::malformed(format!(
+                    "Prop index {idx} out of range for entity {} ({} props)",
+                    self.id,
+                    self.props.len()
+                )));
+            }
+            decode_prop(&mut self.props[idx], reader)?;
+            match self.props[idx].entry.name.as_str() {
+                ENTITY_PLAYER_POSITION_XY_PROP | ENTITY_PLAYER_POSITION_Z_PROP
+                    if self.is_player() =>
+                {
+                    position_updated = true;
                 }
                 ENTITY_OTHER_POSITION_X_PROP
                 | ENTITY_OTHER_POSITION_Y_PROP
                 | ENTITY_OTHER_POSITION_Z_PROP
-                | ENTITY_OTHER_POSITION_ORIGIN_PROP => {
-                    if !self.is_player() {
-                        position_updated = true;
-                    }
+                | ENTITY_OTHER_POSITION_ORIGIN_PROP
+                    if !self.is_player() =>
+                {
+                    position_updated = true;
                 }
                 "m_nModelIndex" => {
-                    if let Some(proj) = GRENADE_PROJECTILES.lock().await.get_mut(&self.id) {
+                    if let Some(proj) = state.grenade_projectiles.lock().get_mut(&self.id) {
                         if let PropertyValueEnum::Integer(Cow::Owned(handle)) =
-                            self.props[*idx as usize].value
+                            self.props[idx].value
                         {
-                            if let Some(wep_type) = GRENADE_MODEL_INDICES.lock().await.get(&handle)
+                            if let Some(wep_type) = state.grenade_model_indices.lock().get(&handle)
                             {
                                 proj.wep_type = wep_type.to_owned();
                             }
@@ -368,20 +373,18 @@ impl Entity {
                     }
                 }
                 "m_hThrower" | "m_hOwnerEntity" => {
-                    if let Some(proj) = GRENADE_PROJECTILES.lock().await.get_mut(&self.id) {
+                    if let Some(proj) = state.grenade_projectiles.lock().get_mut(&self.id) {
                         if let PropertyValueEnum::Integer(Cow::Owned(handle)) =
-                            self.props[*idx as usize].value
+                            self.props[idx].value
                         {
                             if handle != _INVALID_ENTITY_HANDLE {
                                 let entity_id = handle & _ENTITY_HANDLE_INDEX_MASK;
                                 if let Some(person) =
-                                    PLAYERS_BY_ENTITY_ID.lock().await.get(&entity_id)
+                                    state.players_by_entity_id.lock().get(&entity_id)
                                 {
-                                    if self.props[*idx as usize].entry.name == "m_hThrower" {
+                                    if self.props[idx].entry.name == "m_hThrower" {
                                         proj.thrower = Some(person.to_owned());
-                                    } else if self.props[*idx as usize].entry.name
-                                        == "m_hOwnerEntity"
-                                    {
+                                    } else if self.props[idx].entry.name == "m_hOwnerEntity" {
                                         proj.owner = Some(person.to_owned());
                                     }
                                 }
@@ -403,14 +406,10 @@ impl Entity {
 
                     let team = self._property_value_must("m_iTeamNum").as_integer();
 
-                    GLOBAL_DISPATCHER
-                        .lock()
-                        .await
-                        .emit(
-                            "player_left_buyzone",
-                            ((self.id, team), (pos.x.into_owned(), pos.y.into_owned())),
-                        )
-                        .await;
+                    state.emit(
+                        "player_left_buyzone",
+                        ((self.id, team), (pos.x.into_owned(), pos.y.into_owned())),
+                    );
                 }
                 self.is_in_buyzone = is_in_buyzone;
             }
@@ -419,7 +418,7 @@ impl Entity {
                 self.last_flash_duration = 0.0;
                 self.current_flash_frame_agg = 0;
             }
-        } else if let Some(proj) = GRENADE_PROJECTILES.lock().await.get_mut(&self.id) {
+        } else if let Some(proj) = state.grenade_projectiles.lock().get_mut(&self.id) {
             if position_updated {
                 let new_pos = self.get_position();
 
@@ -429,6 +428,8 @@ impl Entity {
                 }
             }
         }
+
+        Ok(())
     }
 
     #[inline]
@@ -498,11 +499,6 @@ impl Entity {
         (self.position)(self)
     }
 
-    #[inline]
-    pub fn on_position_update(_entity: &mut Entity) {
-        todo!();
-    }
-
     #[inline]
     fn _fire_player_pos_update(
         entity: &mut Entity,
@@ -562,7 +558,10 @@ impl Property {
 }
 
 #[inline]
-pub fn decode_prop<T: Read + Seek + Send>(prop: &mut Property, reader: &mut BitReader<T>) {
+pub fn decode_prop<T: Read + Seek + Send>(
+    prop: &mut Property,
+    reader: &mut BitReader<T>,
+) -> Result<(), ParseError> {
     match prop.entry.prop.raw_type {
         0 => {
             prop.value =
@@ -578,9 +577,11 @@ pub fn decode_prop<T: Read + Seek + Send>(prop: &mut Property, reader: &mut BitR
                 PropertyValueEnum::Vector(Cow::Owned(decode_vectorxy(&prop.entry.prop, reader)))
         }
         4 => prop.value = PropertyValueEnum::String(Cow::Owned(decode_string(reader))),
-        5 => prop.value = PropertyValueEnum::Array(decode_array(&prop.entry, reader)),
-        _ => panic!("Unknown prop type {}", prop.entry.prop.raw_type),
+        5 => prop.value = PropertyValueEnum::Array(decode_array(&prop.entry, reader)?),
+        _ => return Err(ParseError::unknown_prop_type(prop.entry.prop.raw_type)),
     }
+
+    Ok(())
 }
 
 #[inline]
@@ -690,12 +691,16 @@ fn decode_string<T: Read + Seek + Send>(reader: &mut BitReader<T>) -> String {
 fn decode_array<T: Read + Seek + Send>(
     fprop: &FlattenedPropEntry,
     reader: &mut BitReader<T>,
-) -> Vec<PropertyValueEnum> {
+) -> Result<Vec<PropertyValueEnum>, ParseError> {
+    let array_elem_prop = fprop.array_elem_prop.clone().ok_or_else(|| {
+        ParseError::malformed(format!("Array prop {} has no element prop", fprop.name))
+    })?;
+
     let num_bits = f64::floor(f64::log2(fprop.prop.num_elems as f64) + 1.0);
     let mut res = vec![PropertyValueEnum::None; reader.read_int(num_bits as usize)];
     let mut tmp = Property {
         entry: FlattenedPropEntry {
-            prop: fprop.array_elem_prop.clone().unwrap(),
+            prop: array_elem_prop,
             ..Default::default()
         },
         value: PropertyValueEnum::None,
@@ -703,9 +708,9 @@ fn decode_array<T: Read + Seek + Send>(
     };
 
     for i in res.iter_mut() {
-        decode_prop(&mut tmp, reader);
+        decode_prop(&mut tmp, reader)?;
         *i = tmp.value.to_owned();
     }
 
-    res
+    Ok(res)
 }
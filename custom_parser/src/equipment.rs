@@ -1,7 +1,6 @@
 use std::borrow::Cow;
 
 use lazy_static::lazy_static;
-use tokio::sync::Mutex;
 
 use ahash::AHashMap as HashMap;
 
@@ -10,8 +9,7 @@ use crate::{
     entity::{Entity, _ENTITY_HANDLE_INDEX_MASK, _INVALID_ENTITY_HANDLE},
     player::{Player, PlayerInfo},
     serverclass::PropertyValueEnum,
-    GRENADE_PROJECTILES, PLAYERS_BY_ENTITY_ID, PLAYERS_BY_STEAM_ID, PLAYER_INFO_BY_STEAM_ID,
-    PLAYER_INFO_BY_USER_ID, SERVER_CLASSES, THROWN_GRENADES,
+    ParserState,
 };
 
 pub const _EQ_CLASSUNKNOWN: i32 = 0;
@@ -87,7 +85,6 @@ pub const EQ_SMOKE: i32 = 505;
 pub const EQ_HE: i32 = 506;
 
 lazy_static! {
-    pub static ref EQUIPMENT_MAPPING: Mutex<HashMap<i32, i32>> = Mutex::new(HashMap::new());
     pub static ref EQUIPMENT_NAME_TO_WEAPON: HashMap<String, i32> = HashMap::from([
         ("ak47".to_string(),                    EQ_AK47),
         ("aug".to_string(),                     EQ_AUG),
@@ -223,32 +220,32 @@ lazy_static! {
 }
 
 #[inline]
-pub async fn map_equipment() {
-    for sc in SERVER_CLASSES.lock().await.iter() {
+pub fn map_equipment(state: &ParserState) {
+    for sc in state.server_classes.lock().iter() {
         match sc.name.as_str() {
             "CC4" => {
-                EQUIPMENT_MAPPING.lock().await.insert(sc.id, EQ_BOMB);
+                state.equipment_mapping.lock().insert(sc.id, EQ_BOMB);
             }
             "CWeaponNOVA" | "CWeaponSawedoff" | "CWeaponXM1014" => {
-                EQUIPMENT_MAPPING
+                state
+                    .equipment_mapping
                     .lock()
-                    .await
                     .insert(sc.id, map_equipment_name(&sc.name[7..].to_lowercase()));
             }
             "CKnife" => {
-                EQUIPMENT_MAPPING.lock().await.insert(sc.id, EQ_KNIFE);
+                state.equipment_mapping.lock().insert(sc.id, EQ_KNIFE);
             }
             "CSnowball" | "CWeaponShield" | "CWeaponZoneRepulsor" => continue,
             _ => {
                 if sc._base_class_exists("DT_WeaponCSBaseGun") {
-                    EQUIPMENT_MAPPING
+                    state
+                        .equipment_mapping
                         .lock()
-                        .await
                         .insert(sc.id, map_equipment_name(&sc.dt_name[9..].to_lowercase()));
                 } else if sc._base_class_exists("DT_BaseCSGrenade") {
-                    EQUIPMENT_MAPPING
+                    state
+                        .equipment_mapping
                         .lock()
-                        .await
                         .insert(sc.id, map_equipment_name(&sc.dt_name[3..].to_lowercase()));
                 }
             }
@@ -262,7 +259,10 @@ pub fn map_equipment_name(mut eq_name: &str) -> i32 {
     if eq_name.contains("knife") || eq_name.contains("bayonet") {
         return EQ_KNIFE;
     }
-    EQUIPMENT_NAME_TO_WEAPON[eq_name].to_owned()
+    EQUIPMENT_NAME_TO_WEAPON
+        .get(eq_name)
+        .copied()
+        .unwrap_or(EQ_UNKNOWN)
 }
 
 #[derive(Clone)]
@@ -305,48 +305,65 @@ pub struct GrenadeProjectile {
     pub wep_type: i32,
 }
 
-pub async fn bind_weapons() {
-    let mut server_classes = SERVER_CLASSES.lock().await;
+pub fn bind_weapons(state: &ParserState) {
+    let mut server_classes = state.server_classes.lock();
     for sc in server_classes.iter_mut() {
         if sc._base_class_exists("DT_WeaponCSBase") && !sc._base_class_exists("DT_BaseCSGrenade") {
             match sc.created_handlers.as_mut() {
                 Some(created_handlers) => {
-                    created_handlers.push(|e| Box::pin(async move { bind_weapon(e).await }));
-                }
-                None => {
-                    sc.created_handlers =
-                        Some(vec![|e| Box::pin(async move { bind_weapon(e).await })])
+                    created_handlers.push(bind_weapon);
                 }
+                None => sc.created_handlers = Some(vec![bind_weapon]),
             };
         } else if sc._base_class_exists("DT_BaseCSGrenade")
             || sc._base_class_exists("DT_BaseGrenade")
         {
             match sc.created_handlers.as_mut() {
-                Some(created_handlers) => created_handlers
-                    .push(|e| Box::pin(async move { bind_grenade_projectiles(e).await })),
-                None => {
-                    sc.created_handlers = Some(vec![|e| {
-                        Box::pin(async move { bind_grenade_projectiles(e).await })
-                    }])
-                }
+                Some(created_handlers) => created_handlers.push(bind_grenade_projectiles),
+                None => sc.created_handlers = Some(vec![bind_grenade_projectiles]),
             }
         }
     }
 }
 
 #[inline]
-async fn bind_weapon(_entity_id: i32) {
-    todo!()
+fn bind_weapon(state: &ParserState, entity: &Entity) {
+    let eq_type = state
+        .equipment_mapping
+        .lock()
+        .get(&entity.server_class.id)
+        .copied()
+        .unwrap_or(EQ_UNKNOWN);
+
+    let owner_entity_id = match entity.property("m_hOwnerEntity").map(|prop| prop.value) {
+        Some(PropertyValueEnum::Integer(Cow::Owned(handle)))
+            if handle != _INVALID_ENTITY_HANDLE =>
+        {
+            handle & _ENTITY_HANDLE_INDEX_MASK
+        }
+        _ => -1,
+    };
+
+    state.weapons.lock().insert(
+        entity.id,
+        Equipment {
+            eq_type,
+            entity_id: entity.id,
+            owner_entity_id,
+            original_string: entity.server_class.name.to_owned(),
+            ..Default::default()
+        },
+    );
 }
 
 #[inline]
-async fn bind_grenade_projectiles(entity_id: i32) {
-    GRENADE_PROJECTILES
+fn bind_grenade_projectiles(state: &ParserState, entity: &Entity) {
+    state
+        .grenade_projectiles
         .lock()
-        .await
-        .entry(entity_id)
+        .entry(entity.id)
         .or_insert(GrenadeProjectile {
-            entity_id,
+            entity_id: entity.id,
             ..Default::default()
         });
 }
@@ -370,15 +387,19 @@ pub fn get_player_weapon(player: &Option<Player>, wep_type: i32) -> Equipment {
 }
 
 #[inline]
-pub async fn _retrieve_potential_thrower_owner(entity: &Entity, proj: &mut GrenadeProjectile) {
+pub fn _retrieve_potential_thrower_owner(
+    state: &ParserState,
+    entity: &Entity,
+    proj: &mut GrenadeProjectile,
+) {
     if let Some(prop) = entity.property("m_hThrower") {
         if let PropertyValueEnum::Integer(Cow::Owned(handle)) = prop.value {
             if handle != _INVALID_ENTITY_HANDLE {
                 let entity_id = handle & _ENTITY_HANDLE_INDEX_MASK;
-                if let Some(player) = PLAYERS_BY_ENTITY_ID.lock().await.get(&entity_id) {
+                if let Some(player) = state.players_by_entity_id.lock().get(&entity_id) {
                     proj.thrower = Some(player.to_owned());
                 } else if let Some(player_info) =
-                    PLAYER_INFO_BY_USER_ID.lock().await.get(&(entity_id - 1))
+                    state.player_info_by_user_id.lock().get(&(entity_id - 1))
                 {
                     proj.thrower_info = Some(player_info.to_owned());
                 }
@@ -388,10 +409,10 @@ pub async fn _retrieve_potential_thrower_owner(entity: &Entity, proj: &mut Grena
         if let PropertyValueEnum::Integer(Cow::Owned(handle)) = prop.value {
             if handle != _INVALID_ENTITY_HANDLE {
                 let entity_id = handle & _ENTITY_HANDLE_INDEX_MASK;
-                if let Some(player) = PLAYERS_BY_ENTITY_ID.lock().await.get(&entity_id) {
+                if let Some(player) = state.players_by_entity_id.lock().get(&entity_id) {
                     proj.owner = Some(player.to_owned());
                 } else if let Some(player_info) =
-                    PLAYER_INFO_BY_USER_ID.lock().await.get(&(entity_id - 1))
+                    state.player_info_by_user_id.lock().get(&(entity_id - 1))
                 {
                     proj.owner_info = Some(player_info.to_owned());
                 }
@@ -401,10 +422,10 @@ pub async fn _retrieve_potential_thrower_owner(entity: &Entity, proj: &mut Grena
         if let PropertyValueEnum::Integer(Cow::Owned(handle)) = prop.value {
             if handle != _INVALID_ENTITY_HANDLE {
                 let entity_id = handle & _ENTITY_HANDLE_INDEX_MASK;
-                if let Some(player) = PLAYERS_BY_ENTITY_ID.lock().await.get(&entity_id) {
+                if let Some(player) = state.players_by_entity_id.lock().get(&entity_id) {
                     proj.owner = Some(player.to_owned());
                 } else if let Some(player_info) =
-                    PLAYER_INFO_BY_USER_ID.lock().await.get(&(entity_id - 1))
+                    state.player_info_by_user_id.lock().get(&(entity_id - 1))
                 {
                     proj.owner_info = Some(player_info.to_owned());
                 }
@@ -414,10 +435,10 @@ pub async fn _retrieve_potential_thrower_owner(entity: &Entity, proj: &mut Grena
         if let PropertyValueEnum::Integer(Cow::Owned(handle)) = prop.value {
             if handle != _INVALID_ENTITY_HANDLE {
                 let entity_id = handle & _ENTITY_HANDLE_INDEX_MASK;
-                if let Some(player) = PLAYERS_BY_ENTITY_ID.lock().await.get(&entity_id) {
+                if let Some(player) = state.players_by_entity_id.lock().get(&entity_id) {
                     proj.owner = Some(player.to_owned());
                 } else if let Some(player_info) =
-                    PLAYER_INFO_BY_USER_ID.lock().await.get(&(entity_id - 1))
+                    state.player_info_by_user_id.lock().get(&(entity_id - 1))
                 {
                     proj.owner_info = Some(player_info.to_owned());
                 }
@@ -433,9 +454,9 @@ pub async fn _retrieve_potential_thrower_owner(entity: &Entity, proj: &mut Grena
         ) = (lo_prop.value, hi_prop.value)
         {
             let steam_id: u64 = (lo as u64) | ((hi as u64) << 32);
-            if let Some(player) = PLAYERS_BY_STEAM_ID.lock().await.get(&steam_id) {
+            if let Some(player) = state.players_by_steam_id.lock().get(&steam_id) {
                 proj.owner = Some(player.to_owned());
-            } else if let Some(player_info) = PLAYER_INFO_BY_STEAM_ID.lock().await.get(&steam_id) {
+            } else if let Some(player_info) = state.player_info_by_steam_id.lock().get(&steam_id) {
                 proj.owner_info = Some(player_info.to_owned());
             }
         }
@@ -443,9 +464,9 @@ pub async fn _retrieve_potential_thrower_owner(entity: &Entity, proj: &mut Grena
 }
 
 #[inline]
-pub async fn _add_thrown_grenade(player: &Option<Player>, wep: &Equipment) {
+pub fn _add_thrown_grenade(state: &ParserState, player: &Option<Player>, wep: &Equipment) {
     if let Some(pl) = player {
-        let mut thrown_grenades = THROWN_GRENADES.lock().await;
+        let mut thrown_grenades = state.thrown_grenades.lock();
         if let Some(nades) = thrown_grenades.get_mut(&pl.entity_id) {
             nades.push(wep.to_owned());
         } else {
@@ -455,9 +476,9 @@ pub async fn _add_thrown_grenade(player: &Option<Player>, wep: &Equipment) {
 }
 
 #[inline]
-pub async fn _delete_thrown_grenade(player: &Option<Player>, wep_type: i32) {
+pub fn _delete_thrown_grenade(state: &ParserState, player: &Option<Player>, wep_type: i32) {
     if let Some(pl) = player {
-        if let Some(weapons) = THROWN_GRENADES.lock().await.get_mut(&pl.entity_id) {
+        if let Some(weapons) = state.thrown_grenades.lock().get_mut(&pl.entity_id) {
             let mut index_to_remove: Option<usize> = None;
             for (i, weapon) in weapons.iter_mut().enumerate() {
                 if is_same_equipment(wep_type, weapon.eq_type) {
@@ -0,0 +1,191 @@
+use std::{any::Any, fmt};
+
+use custom_bitreader::ChunkBoundaryError;
+
+#[derive(Debug)]
+pub enum ParseError {
+    Io(std::io::Error),
+    InvalidHeader(String),
+    Truncated {
+        tick: isize,
+        offset: usize,
+    },
+    ChunkBoundary {
+        tick: isize,
+        offset: usize,
+        expected: usize,
+        actual: usize,
+    },
+    UnknownCommand {
+        tick: isize,
+        offset: usize,
+        command: u8,
+    },
+    Decode {
+        tick: isize,
+        offset: usize,
+        message: &'static str,
+        source: prost::DecodeError,
+    },
+    UnknownPropType {
+        tick: isize,
+        offset: usize,
+        prop_type: i32,
+    },
+    Malformed {
+        tick: isize,
+        offset: usize,
+        reason: String,
+    },
+    // The parse panicked; only `Parser::parse_many` catches these.
+    Panicked(String),
+}
+
+impl ParseError {
+    pub(crate) fn decode(message: &'static str, source: prost::DecodeError) -> Self {
+        Self::Decode {
+            tick: -1,
+            offset: 0,
+            message,
+            source,
+        }
+    }
+
+    pub(crate) fn unknown_prop_type(prop_type: i32) -> Self {
+        Self::UnknownPropType {
+            tick: -1,
+            offset: 0,
+            prop_type,
+        }
+    }
+
+    pub(crate) fn malformed<S: Into<String>>(reason: S) -> Self {
+        Self::Malformed {
+            tick: -1,
+            offset: 0,
+            reason: reason.into(),
+        }
+    }
+
+    pub(crate) fn panicked(payload: Box<dyn Any + Send>) -> Self {
+        let message = match payload.downcast::<String>() {
+            Ok(message) => *message,
+            Err(payload) => match payload.downcast::<&'static str>() {
+                Ok(message) => message.to_string(),
+                Err(_) => "unknown panic".to_string(),
+            },
+        };
+        Self::Panicked(message)
+    }
+
+    // Stamps the error with the tick and byte offset the parser was at when it surfaced.
+    pub(crate) fn at(mut self, at_tick: isize, at_offset: usize) -> Self {
+        match &mut self {
+            Self::Io(_) | Self::InvalidHeader(_) | Self::Panicked(_) => {}
+            Self::Truncated { tick, offset }
+            | Self::ChunkBoundary { tick, offset, .. }
+            | Self::UnknownCommand { tick, offset, .. }
+            | Self::Decode { tick, offset, .. }
+            | Self::UnknownPropType { tick, offset, .. }
+            | Self::Malformed { tick, offset, .. } => {
+                *tick = at_tick;
+                *offset = at_offset;
+            }
+        }
+        self
+    }
+
+    pub fn tick(&self) -> Option<isize> {
+        match self {
+            Self::Io(_) | Self::InvalidHeader(_) | Self::Panicked(_) => None,
+            Self::Truncated { tick, .. }
+            | Self::ChunkBoundary { tick, .. }
+            | Self::UnknownCommand { tick, .. }
+            | Self::Decode { tick, .. }
+            | Self::UnknownPropType { tick, .. }
+            | Self::Malformed { tick, .. } => Some(*tick),
+        }
+    }
+
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            Self::Io(_) | Self::InvalidHeader(_) | Self::Panicked(_) => None,
+            Self::Truncated { offset, .. }
+            | Self::ChunkBoundary { offset, .. }
+            | Self::UnknownCommand { offset, .. }
+            | Self::Decode { offset, .. }
+            | Self::UnknownPropType { offset, .. }
+            | Self::Malformed { offset, .. } => Some(*offset),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "io error: {e}"),
+            Self::InvalidHeader(reason) => write!(f, "invalid demo header: {reason}"),
+            Self::Panicked(message) => write!(f, "parser panicked: {message}"),
+            Self::Truncated { tick, offset } => {
+                write!(f, "demo is truncated (tick {tick}, byte {offset})")
+            }
+            Self::ChunkBoundary {
+                tick,
+                offset,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "chunk boundary mismatch, expected bit {expected} got {actual} (tick {tick}, byte {offset})"
+            ),
+            Self::UnknownCommand {
+                tick,
+                offset,
+                command,
+            } => write!(f, "unknown packet command {command} (tick {tick}, byte {offset})"),
+            Self::Decode {
+                tick,
+                offset,
+                message,
+                source,
+            } => write!(f, "{message}: {source} (tick {tick}, byte {offset})"),
+            Self::UnknownPropType {
+                tick,
+                offset,
+                prop_type,
+            } => write!(f, "unknown prop type {prop_type} (tick {tick}, byte {offset})"),
+            Self::Malformed {
+                tick,
+                offset,
+                reason,
+            } => write!(f, "{reason} (tick {tick}, byte {offset})"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Decode { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ParseError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<ChunkBoundaryError> for ParseError {
+    fn from(e: ChunkBoundaryError) -> Self {
+        Self::ChunkBoundary {
+            tick: -1,
+            offset: 0,
+            expected: e.expected,
+            actual: e.actual,
+        }
+    }
+}
@@ -1,19 +1,31 @@
 use prost::Message;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
 
-use crate::gameevent::{CsvcMsgGameEvent, GameEvent};
+use crate::{
+    gameevent::{CsvcMsgGameEvent, GameEvent},
+    serializers::string_float_serializer,
+    ParseError,
+};
 
 pub mod macros {
     #[macro_export]
     macro_rules! impl_event {
         ($name:ident) => {
-            impl From<(GameEvent, CsvcMsgGameEvent)> for $name {
-                fn from(e: (GameEvent, CsvcMsgGameEvent)) -> Self {
+            impl TryFrom<(&GameEvent, &CsvcMsgGameEvent)> for $name {
+                type Error = ParseError;
+
+                // Keys the event does not have keep their default value.
+                fn try_from(e: (&GameEvent, &CsvcMsgGameEvent)) -> Result<Self, Self::Error> {
                     let game_event = e.0;
                     let msg = e.1;
 
-                    serde_json::from_value::<$name>(json!(game_event.to_object(&msg))).unwrap()
+                    game_event.deserialize_keys::<$name>(msg).map_err(|e| {
+                        ParseError::malformed(format!(
+                            "Game event {} does not fit {}: {e}",
+                            game_event.name,
+                            stringify!($name)
+                        ))
+                    })
                 }
             }
         };
@@ -23,46 +35,49 @@ pub mod macros {
 }
 
 #[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
 pub struct PlayerDeath {
     #[prost(string, tag = "1")]
-    weapon_fauxitemid: String,
+    pub weapon_fauxitemid: String,
     #[prost(string, tag = "2")]
-    weapon_itemid: String,
+    pub weapon_itemid: String,
     #[prost(bool, tag = "3")]
-    noscope: bool,
+    pub noscope: bool,
     #[prost(int32, tag = "4")]
-    revenge: i32,
+    pub revenge: i32,
     #[prost(int32, tag = "5")]
-    assister: i32,
+    pub assister: i32,
     #[prost(int32, tag = "6")]
-    penetrated: i32,
+    pub penetrated: i32,
     #[prost(bool, tag = "7")]
-    noreplay: bool,
+    pub noreplay: bool,
     #[prost(int32, tag = "8")]
-    attacker: i32,
+    pub attacker: i32,
     #[prost(bool, tag = "9")]
-    headshot: bool,
+    pub headshot: bool,
     #[prost(bool, tag = "10")]
-    thrusmoke: bool,
+    pub thrusmoke: bool,
     #[prost(bool, tag = "11")]
-    attackerblind: bool,
+    pub attackerblind: bool,
     #[prost(int32, tag = "12")]
-    userid: i32,
+    pub userid: i32,
     #[prost(string, tag = "13")]
-    weapon: String,
+    pub weapon: String,
     #[prost(int32, tag = "14")]
-    dominated: i32,
-    #[prost(string, tag = "15")]
-    distance: String,
+    pub dominated: i32,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "15")]
+    pub distance: f64,
     #[prost(bool, tag = "16")]
-    assistedflash: bool,
+    pub assistedflash: bool,
     #[prost(int32, tag = "17")]
-    wipe: i32,
+    pub wipe: i32,
     #[prost(string, tag = "18")]
-    weapon_originalowner_xuid: String,
+    pub weapon_originalowner_xuid: String,
 }
 
 #[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
 pub struct RoundEnd {
     #[prost(int32, tag = "1")]
     pub winner: i32,
@@ -79,46 +94,50 @@ pub struct RoundEnd {
 }
 
 #[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
 pub struct PlayerHurt {
     #[prost(int32, tag = "1")]
-    dmg_health: i32,
+    pub dmg_health: i32,
     #[prost(int32, tag = "2")]
-    health: i32,
+    pub health: i32,
     #[prost(string, tag = "3")]
-    weapon: String,
+    pub weapon: String,
     #[prost(int32, tag = "4")]
-    attacker: i32,
+    pub attacker: i32,
     #[prost(int32, tag = "5")]
-    userid: i32,
+    pub userid: i32,
     #[prost(int32, tag = "6")]
-    armor: i32,
+    pub armor: i32,
     #[prost(int32, tag = "7")]
-    dmg_armor: i32,
+    pub dmg_armor: i32,
     #[prost(int32, tag = "8")]
-    hitgroup: i32,
+    pub hitgroup: i32,
 }
 
 #[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
 pub struct WeaponFire {
     #[prost(int32, tag = "1")]
-    userid: i32,
+    pub userid: i32,
     #[prost(string, tag = "2")]
-    weapon: String,
+    pub weapon: String,
     #[prost(bool, tag = "3")]
-    silenced: bool,
+    pub silenced: bool,
 }
 
 #[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
 pub struct RoundStart {
     #[prost(string, tag = "1")]
-    objective: String,
+    pub objective: String,
     #[prost(int32, tag = "2")]
-    fraglimit: i32,
+    pub fraglimit: i32,
     #[prost(int32, tag = "3")]
-    timelimit: i32,
+    pub timelimit: i32,
 }
 
 #[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
 pub struct PlayerSpawn {
     #[prost(int32, tag = "1")]
     pub teamnum: i32,
@@ -127,59 +146,755 @@ pub struct PlayerSpawn {
 }
 
 #[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
 pub struct PlayerFootstep {
     #[prost(int32, tag = "1")]
     pub userid: i32,
 }
 
 #[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
 pub struct SmokeGrenadeDetonate {
     #[prost(int32, tag = "1")]
-    entityid: i32,
+    pub entityid: i32,
     #[prost(int32, tag = "2")]
-    userid: i32,
-    #[prost(string, tag = "3")]
-    x: String,
-    #[prost(string, tag = "4")]
-    y: String,
-    #[prost(string, tag = "5")]
-    z: String,
+    pub userid: i32,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "3")]
+    pub x: f64,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "4")]
+    pub y: f64,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "5")]
+    pub z: f64,
 }
 
 #[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
 pub struct SmokeGrenadeExpired {
     #[prost(int32, tag = "1")]
-    entityid: i32,
+    pub entityid: i32,
     #[prost(int32, tag = "2")]
-    userid: i32,
-    #[prost(string, tag = "3")]
-    x: String,
-    #[prost(string, tag = "4")]
-    y: String,
-    #[prost(string, tag = "5")]
-    z: String,
+    pub userid: i32,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "3")]
+    pub x: f64,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "4")]
+    pub y: f64,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "5")]
+    pub z: f64,
 }
 
 #[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
 pub struct FlashbangDetonate {
     #[prost(int32, tag = "1")]
     pub entityid: i32,
     #[prost(int32, tag = "2")]
     pub userid: i32,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "3")]
+    pub x: f64,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "4")]
+    pub y: f64,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "5")]
+    pub z: f64,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct BombPlanted {
+    #[prost(int32, tag = "1")]
+    pub site: i32,
+    #[prost(int32, tag = "2")]
+    pub userid: i32,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct BombBeginPlant {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+    #[prost(int32, tag = "2")]
+    pub site: i32,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct BombAbortPlant {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+    #[prost(int32, tag = "2")]
+    pub site: i32,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct BombDefused {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+    #[prost(int32, tag = "2")]
+    pub site: i32,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct BombExploded {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+    #[prost(int32, tag = "2")]
+    pub site: i32,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct BombDropped {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+    #[prost(int32, tag = "2")]
+    pub entindex: i32,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct BombPickup {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct BombBeginDefuse {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+    #[prost(bool, tag = "2")]
+    pub haskit: bool,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct BombAbortDefuse {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct ItemPurchase {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+    #[prost(int32, tag = "2")]
+    pub team: i32,
+    #[prost(int32, tag = "3")]
+    pub loadout: i32,
+    #[prost(string, tag = "4")]
+    pub weapon: String,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct ItemPickup {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+    #[prost(string, tag = "2")]
+    pub item: String,
+    #[prost(bool, tag = "3")]
+    pub silent: bool,
+    #[prost(int32, tag = "4")]
+    pub defindex: i32,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct ItemRemove {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+    #[prost(string, tag = "2")]
+    pub item: String,
+    #[prost(int32, tag = "3")]
+    pub defindex: i32,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct ItemEquip {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+    #[prost(string, tag = "2")]
+    pub item: String,
+    #[prost(int32, tag = "3")]
+    pub defindex: i32,
+    #[prost(bool, tag = "4")]
+    pub canzoom: bool,
+    #[prost(bool, tag = "5")]
+    pub hassilencer: bool,
+    #[prost(bool, tag = "6")]
+    pub issilenced: bool,
+    #[prost(bool, tag = "7")]
+    pub hastracers: bool,
+    #[prost(int32, tag = "8")]
+    pub weptype: i32,
+    #[prost(bool, tag = "9")]
+    pub ispainted: bool,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct AmmoPickup {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+    #[prost(string, tag = "2")]
+    pub item: String,
+    #[prost(int32, tag = "3")]
+    pub index: i32,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct PlayerBlind {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+    #[prost(int32, tag = "2")]
+    pub attacker: i32,
+    #[prost(int32, tag = "3")]
+    pub entityid: i32,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "4")]
+    pub blind_duration: f64,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct PlayerJump {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct PlayerFallDamage {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "2")]
+    pub damage: f64,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct HeGrenadeDetonate {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+    #[prost(int32, tag = "2")]
+    pub entityid: i32,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "3")]
+    pub x: f64,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "4")]
+    pub y: f64,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "5")]
+    pub z: f64,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct MolotovDetonate {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "2")]
+    pub x: f64,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "3")]
+    pub y: f64,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "4")]
+    pub z: f64,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct DecoyStarted {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+    #[prost(int32, tag = "2")]
+    pub entityid: i32,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "3")]
+    pub x: f64,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "4")]
+    pub y: f64,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "5")]
+    pub z: f64,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct DecoyDetonate {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+    #[prost(int32, tag = "2")]
+    pub entityid: i32,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "3")]
+    pub x: f64,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "4")]
+    pub y: f64,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "5")]
+    pub z: f64,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct DecoyFiring {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+    #[prost(int32, tag = "2")]
+    pub entityid: i32,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "3")]
+    pub x: f64,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "4")]
+    pub y: f64,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "5")]
+    pub z: f64,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct TaGrenadeDetonate {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+    #[prost(int32, tag = "2")]
+    pub entityid: i32,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "3")]
+    pub x: f64,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "4")]
+    pub y: f64,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "5")]
+    pub z: f64,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct InfernoStartBurn {
+    #[prost(int32, tag = "1")]
+    pub entityid: i32,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "2")]
+    pub x: f64,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "3")]
+    pub y: f64,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "4")]
+    pub z: f64,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct InfernoExpire {
+    #[prost(int32, tag = "1")]
+    pub entityid: i32,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "2")]
+    pub x: f64,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "3")]
+    pub y: f64,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "4")]
+    pub z: f64,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct InfernoExtinguish {
+    #[prost(int32, tag = "1")]
+    pub entityid: i32,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "2")]
+    pub x: f64,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "3")]
+    pub y: f64,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "4")]
+    pub z: f64,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct GrenadeThrown {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+    #[prost(string, tag = "2")]
+    pub weapon: String,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct GrenadeBounce {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "2")]
+    pub x: f64,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "3")]
+    pub y: f64,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "4")]
+    pub z: f64,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct BulletImpact {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "2")]
+    pub x: f64,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "3")]
+    pub y: f64,
+    #[serde(with = "string_float_serializer")]
+    #[prost(double, tag = "4")]
+    pub z: f64,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct PlayerConnect {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(int32, tag = "2")]
+    pub index: i32,
+    #[prost(int32, tag = "3")]
+    pub userid: i32,
+    #[prost(string, tag = "4")]
+    pub networkid: String,
+    #[prost(string, tag = "5")]
+    pub address: String,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct PlayerConnectFull {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+    #[prost(int32, tag = "2")]
+    pub index: i32,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct PlayerDisconnect {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+    #[prost(string, tag = "2")]
+    pub reason: String,
     #[prost(string, tag = "3")]
-    pub x: String,
+    pub name: String,
     #[prost(string, tag = "4")]
-    pub y: String,
+    pub networkid: String,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct PlayerTeam {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+    #[prost(int32, tag = "2")]
+    pub team: i32,
+    #[prost(int32, tag = "3")]
+    pub oldteam: i32,
+    #[prost(bool, tag = "4")]
+    pub disconnect: bool,
+    #[prost(bool, tag = "5")]
+    pub autoteam: bool,
+    #[prost(bool, tag = "6")]
+    pub silent: bool,
+    #[prost(bool, tag = "7")]
+    pub isbot: bool,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct PlayerChangeName {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+    #[prost(string, tag = "2")]
+    pub oldname: String,
+    #[prost(string, tag = "3")]
+    pub newname: String,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct PlayerGivenC4 {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct CsWinPanelRound {
+    #[prost(bool, tag = "1")]
+    pub show_timer_defend: bool,
+    #[prost(bool, tag = "2")]
+    pub show_timer_attack: bool,
+    #[prost(int32, tag = "3")]
+    pub timer_time: i32,
+    #[prost(int32, tag = "4")]
+    pub final_event: i32,
     #[prost(string, tag = "5")]
-    pub z: String,
+    pub funfact_token: String,
+    #[prost(int32, tag = "6")]
+    pub funfact_player: i32,
+    #[prost(int32, tag = "7")]
+    pub funfact_data1: i32,
+    #[prost(int32, tag = "8")]
+    pub funfact_data2: i32,
+    #[prost(int32, tag = "9")]
+    pub funfact_data3: i32,
 }
 
+// Empty on purpose: CS:GO's descriptor for cs_win_panel_match lists no keys, so there is
+// nothing to deserialize. The final score is on the team entities.
 #[derive(Deserialize, Serialize, Message)]
-pub struct BombPlanted {
+#[serde(default)]
+pub struct CsWinPanelMatch {}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct RoundMvp {
     #[prost(int32, tag = "1")]
-    pub site: i32,
+    pub userid: i32,
+    #[prost(int32, tag = "2")]
+    pub reason: i32,
+    #[prost(int32, tag = "3")]
+    pub value: i32,
+    #[prost(int32, tag = "4")]
+    pub musickitmvps: i32,
+    #[prost(int32, tag = "5")]
+    pub nomusic: i32,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct MatchEndConditions {
+    #[prost(int32, tag = "1")]
+    pub frags: i32,
+    #[prost(int32, tag = "2")]
+    pub max_rounds: i32,
+    #[prost(int32, tag = "3")]
+    pub win_rounds: i32,
+    #[prost(int32, tag = "4")]
+    pub time: i32,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct WeaponReload {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct WeaponZoom {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct WeaponFireOnEmpty {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+    #[prost(string, tag = "2")]
+    pub weapon: String,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct WeaponOutOfAmmo {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct SilencerDetach {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct InspectWeapon {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct BotTakeover {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
     #[prost(int32, tag = "2")]
+    pub botid: i32,
+    #[prost(int32, tag = "3")]
+    pub index: i32,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct EnterBuyzone {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+    #[prost(bool, tag = "2")]
+    pub canbuy: bool,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct ExitBuyzone {
+    #[prost(int32, tag = "1")]
     pub userid: i32,
+    #[prost(bool, tag = "2")]
+    pub canbuy: bool,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct EnterBombzone {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+    #[prost(bool, tag = "2")]
+    pub hasbomb: bool,
+    #[prost(bool, tag = "3")]
+    pub isplanted: bool,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct ExitBombzone {
+    #[prost(int32, tag = "1")]
+    pub userid: i32,
+    #[prost(bool, tag = "2")]
+    pub hasbomb: bool,
+    #[prost(bool, tag = "3")]
+    pub isplanted: bool,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct DoorMoving {
+    #[prost(int32, tag = "1")]
+    pub entindex: i32,
+    #[prost(int32, tag = "2")]
+    pub userid: i32,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+#[serde(default)]
+pub struct OtherDeath {
+    #[prost(int32, tag = "1")]
+    pub otherid: i32,
+    #[prost(string, tag = "2")]
+    pub othertype: String,
+    #[prost(int32, tag = "3")]
+    pub attacker: i32,
+    #[prost(string, tag = "4")]
+    pub weapon: String,
+    #[prost(string, tag = "5")]
+    pub weapon_itemid: String,
+    #[prost(string, tag = "6")]
+    pub weapon_fauxitemid: String,
+    #[prost(string, tag = "7")]
+    pub weapon_originalowner_xuid: String,
+    #[prost(bool, tag = "8")]
+    pub headshot: bool,
+    #[prost(int32, tag = "9")]
+    pub penetrated: i32,
+    #[prost(bool, tag = "10")]
+    pub noscope: bool,
+    #[prost(bool, tag = "11")]
+    pub thrusmoke: bool,
+    #[prost(bool, tag = "12")]
+    pub attackerblind: bool,
+}
+
+// A player referenced by a game event, as they were on the tick the event fired.
+#[derive(Deserialize, Serialize, Message)]
+pub struct EventPlayer {
+    #[prost(int32, tag = "1")]
+    pub user_id: i32,
+    #[prost(string, tag = "2")]
+    pub name: String,
+    #[prost(uint64, tag = "3")]
+    pub steam_id: u64,
+    #[prost(int32, tag = "4")]
+    pub team: i32,
+    #[prost(double, tag = "5")]
+    pub x: f64,
+    #[prost(double, tag = "6")]
+    pub y: f64,
+    #[prost(double, tag = "7")]
+    pub z: f64,
+    #[prost(int32, tag = "8")]
+    pub active_weapon: i32,
+    #[prost(string, tag = "9")]
+    pub active_weapon_name: String,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+pub struct PlayerDeathEnriched {
+    #[prost(message, optional, tag = "1")]
+    pub event: Option<PlayerDeath>,
+    #[prost(message, optional, tag = "2")]
+    pub victim: Option<EventPlayer>,
+    #[prost(message, optional, tag = "3")]
+    pub attacker: Option<EventPlayer>,
+    #[prost(message, optional, tag = "4")]
+    pub assister: Option<EventPlayer>,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+pub struct PlayerHurtEnriched {
+    #[prost(message, optional, tag = "1")]
+    pub event: Option<PlayerHurt>,
+    #[prost(message, optional, tag = "2")]
+    pub victim: Option<EventPlayer>,
+    #[prost(message, optional, tag = "3")]
+    pub attacker: Option<EventPlayer>,
+}
+
+#[derive(Deserialize, Serialize, Message)]
+pub struct WeaponFireEnriched {
+    #[prost(message, optional, tag = "1")]
+    pub event: Option<WeaponFire>,
+    #[prost(message, optional, tag = "2")]
+    pub player: Option<EventPlayer>,
 }
 
 macros::impl_event!(PlayerDeath);
@@ -193,3 +908,54 @@ macros::impl_event!(SmokeGrenadeDetonate);
 macros::impl_event!(SmokeGrenadeExpired);
 macros::impl_event!(FlashbangDetonate);
 macros::impl_event!(BombPlanted);
+macros::impl_event!(BombBeginPlant);
+macros::impl_event!(BombAbortPlant);
+macros::impl_event!(BombDefused);
+macros::impl_event!(BombExploded);
+macros::impl_event!(BombDropped);
+macros::impl_event!(BombPickup);
+macros::impl_event!(BombBeginDefuse);
+macros::impl_event!(BombAbortDefuse);
+macros::impl_event!(ItemPurchase);
+macros::impl_event!(ItemPickup);
+macros::impl_event!(ItemRemove);
+macros::impl_event!(ItemEquip);
+macros::impl_event!(AmmoPickup);
+macros::impl_event!(PlayerBlind);
+macros::impl_event!(PlayerJump);
+macros::impl_event!(PlayerFallDamage);
+macros::impl_event!(HeGrenadeDetonate);
+macros::impl_event!(MolotovDetonate);
+macros::impl_event!(DecoyStarted);
+macros::impl_event!(DecoyDetonate);
+macros::impl_event!(DecoyFiring);
+macros::impl_event!(TaGrenadeDetonate);
+macros::impl_event!(InfernoStartBurn);
+macros::impl_event!(InfernoExpire);
+macros::impl_event!(InfernoExtinguish);
+macros::impl_event!(GrenadeThrown);
+macros::impl_event!(GrenadeBounce);
+macros::impl_event!(BulletImpact);
+macros::impl_event!(PlayerConnect);
+macros::impl_event!(PlayerConnectFull);
+macros::impl_event!(PlayerDisconnect);
+macros::impl_event!(PlayerTeam);
+macros::impl_event!(PlayerChangeName);
+macros::impl_event!(PlayerGivenC4);
+macros::impl_event!(CsWinPanelRound);
+macros::impl_event!(CsWinPanelMatch);
+macros::impl_event!(RoundMvp);
+macros::impl_event!(MatchEndConditions);
+macros::impl_event!(WeaponReload);
+macros::impl_event!(WeaponZoom);
+macros::impl_event!(WeaponFireOnEmpty);
+macros::impl_event!(WeaponOutOfAmmo);
+macros::impl_event!(SilencerDetach);
+macros::impl_event!(InspectWeapon);
+macros::impl_event!(BotTakeover);
+macros::impl_event!(EnterBuyzone);
+macros::impl_event!(ExitBuyzone);
+macros::impl_event!(EnterBombzone);
+macros::impl_event!(ExitBombzone);
+macros::impl_event!(DoorMoving);
+macros::impl_event!(OtherDeath);
@@ -1,28 +1,9 @@
 use ahash::AHashMap as HashMap;
 use prost::Message;
-use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
-
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum GameEventType {
-    PlayerDeath,
-    PlayerConnect,
-    InfernoExpire,
-    InfernoStartBurn,
-    Unknown,
-}
-
-impl From<String> for GameEventType {
-    fn from(s: String) -> Self {
-        match s.as_str() {
-            "player_death" => Self::PlayerDeath,
-            "inferno_expire" => Self::InfernoExpire,
-            "inferno_startburn" => Self::InfernoStartBurn,
-            "player_connect" => Self::PlayerConnect,
-            _ => Self::Unknown,
-        }
-    }
-}
+use serde::{
+    de::{self, value::MapDeserializer, DeserializeOwned, IntoDeserializer, Visitor},
+    forward_to_deserialize_any, Deserialize, Deserializer, Serialize,
+};
 
 #[derive(Clone, Serialize, Deserialize, Message)]
 pub struct GameEvent {
@@ -45,44 +26,107 @@ impl From<DescriptorT> for GameEvent {
 }
 
 impl GameEvent {
-    pub fn to_object(&self, event_msg: &CsvcMsgGameEvent) -> HashMap<String, Value> {
-        let mut event: HashMap<String, Value> = HashMap::new();
-        for i in 0..self.key_names.len() {
-            let key_name = &self.key_names[i];
-            let value = &event_msg.keys[i];
-
-            let event_value = match value.r#type {
-                1 => json!(value.val_string),
-                2 => json!(value.val_float.to_string()),
-                3 => json!(value.val_long),
-                4 => json!(value.val_short),
-                5 => json!(value.val_byte),
-                6 => json!(value.val_bool),
-                7 => json!(value.val_uint64),
-                8 => json!(value.val_wstring),
-                _ => json!({}),
-            };
-            event.insert(key_name.to_string(), event_value);
+    // Builds `T` straight from the keys, matching them to fields by name. Keys of an unknown
+    // type are left out.
+    pub fn deserialize_keys<T: DeserializeOwned>(
+        &self,
+        event_msg: &CsvcMsgGameEvent,
+    ) -> Result<T, de::value::Error> {
+        let keys = self
+            .key_names
+            .iter()
+            .zip(&event_msg.keys)
+            .map(|(key_name, value)| (key_name.as_str(), GameEventValue::from(value)))
+            .filter(|(_, value)| !matches!(value, GameEventValue::Unknown(_)));
+        T::deserialize(MapDeserializer::new(keys))
+    }
+
+    pub fn to_values(&self, event_msg: &CsvcMsgGameEvent) -> HashMap<String, GameEventValue> {
+        self.key_names
+            .iter()
+            .zip(&event_msg.keys)
+            .map(|(key_name, value)| (key_name.to_string(), GameEventValue::from(value)))
+            .collect()
+    }
+}
+
+// Value of a single game event key, keeping the type the server sent it with.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum GameEventValue {
+    String(String),
+    Float(f32),
+    Long(i32),
+    Short(i32),
+    Byte(i32),
+    Bool(bool),
+    Uint64(u64),
+    WString(String),
+    Unknown(i32),
+}
+
+impl From<&KeyT> for GameEventValue {
+    fn from(key: &KeyT) -> Self {
+        match key.r#type {
+            1 => GameEventValue::String(key.val_string.to_owned()),
+            2 => GameEventValue::Float(key.val_float),
+            3 => GameEventValue::Long(key.val_long),
+            4 => GameEventValue::Short(key.val_short),
+            5 => GameEventValue::Byte(key.val_byte),
+            6 => GameEventValue::Bool(key.val_bool),
+            7 => GameEventValue::Uint64(key.val_uint64),
+            8 => GameEventValue::WString(String::from_utf8_lossy(&key.val_wstring).into_owned()),
+            t => GameEventValue::Unknown(t),
         }
+    }
+}
 
-        event
+impl<'de> IntoDeserializer<'de, de::value::Error> for GameEventValue {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
     }
+}
 
-    pub fn into_type<T: From<(GameEvent, CsvcMsgGameEvent)> + Message>(
-        &self,
-        event_msg: CsvcMsgGameEvent,
-    ) -> Vec<u8> {
-        let mut buf = Vec::new();
-        T::from((self.to_owned(), event_msg))
-            .encode(&mut buf)
-            .expect("Failed to encode GameEvent");
-        buf
+impl<'de> Deserializer<'de> for GameEventValue {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            GameEventValue::String(v) | GameEventValue::WString(v) => visitor.visit_string(v),
+            GameEventValue::Float(v) => visitor.visit_f32(v),
+            GameEventValue::Long(v) | GameEventValue::Short(v) | GameEventValue::Byte(v) => {
+                visitor.visit_i32(v)
+            }
+            GameEventValue::Bool(v) => visitor.visit_bool(v),
+            GameEventValue::Uint64(v) => visitor.visit_u64(v),
+            GameEventValue::Unknown(_) => visitor.visit_unit(),
+        }
+    }
+
+    // Some servers send flags as bytes.
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            GameEventValue::Long(v) | GameEventValue::Short(v) | GameEventValue::Byte(v) => {
+                visitor.visit_bool(v != 0)
+            }
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
     }
 }
 
-#[derive(Clone, Serialize, Deserialize)]
-pub struct GameEventObject {
-    pub value: HashMap<String, Value>,
+// Every game event, known to the parser or not, as emitted on the "game_event" channel.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UntypedGameEvent {
+    pub name: String,
+    pub tick: isize,
+    pub keys: HashMap<String, GameEventValue>,
 }
 
 #[derive(Clone, PartialEq, Serialize, Deserialize, Message)]
@@ -142,3 +186,76 @@ pub struct DescriptorKeyT {
     #[prost(string, tag = "2")]
     pub name: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::PlayerDeath;
+
+    fn key(r#type: i32) -> KeyT {
+        KeyT {
+            r#type,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn deserialize_keys_fills_fields_by_name() {
+        let game_event = GameEvent {
+            name: "player_death".to_string(),
+            id: 1,
+            key_names: ["userid", "headshot", "weapon", "distance", "wipe"]
+                .map(String::from)
+                .to_vec(),
+        };
+        let msg = CsvcMsgGameEvent {
+            keys: vec![
+                KeyT {
+                    val_short: 7,
+                    ..key(4)
+                },
+                KeyT {
+                    val_byte: 1,
+                    ..key(5)
+                },
+                KeyT {
+                    val_string: "ak47".to_string(),
+                    ..key(1)
+                },
+                KeyT {
+                    val_float: 2.5,
+                    ..key(2)
+                },
+                key(42),
+            ],
+            ..Default::default()
+        };
+
+        let death: PlayerDeath = game_event.deserialize_keys(&msg).unwrap();
+        assert_eq!(death.userid, 7);
+        assert!(death.headshot);
+        assert_eq!(death.weapon, "ak47");
+        assert_eq!(death.distance, 2.5);
+        // Missing and unknown keys keep their default.
+        assert_eq!(death.attacker, 0);
+        assert_eq!(death.wipe, 0);
+    }
+
+    #[test]
+    fn deserialize_keys_rejects_mismatched_types() {
+        let game_event = GameEvent {
+            name: "player_death".to_string(),
+            id: 1,
+            key_names: vec!["userid".to_string()],
+        };
+        let msg = CsvcMsgGameEvent {
+            keys: vec![KeyT {
+                val_string: "x".to_string(),
+                ..key(1)
+            }],
+            ..Default::default()
+        };
+
+        assert!(game_event.deserialize_keys::<PlayerDeath>(&msg).is_err());
+    }
+}
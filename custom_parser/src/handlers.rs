@@ -0,0 +1,48 @@
+use std::any::{Any, TypeId};
+
+use ahash::AHashMap as HashMap;
+
+use crate::ParserState;
+
+// Where in the demo an event was raised. The state is only borrowed for the duration of the
+// handler; none of its locks are held while handlers run.
+pub struct EventContext<'a> {
+    pub tick: isize,
+    pub round: i32,
+    pub state: &'a ParserState,
+}
+
+type Handler<T> = Box<dyn FnMut(&T, &EventContext<'_>) + Send>;
+
+// Handlers keyed by the type of event they take. They run on the parsing task, one after the
+// other in the order they were added, and get the event by reference.
+#[derive(Default)]
+pub struct HandlerRegistry {
+    handlers: HashMap<TypeId, Box<dyn Any + Send>>,
+}
+
+impl HandlerRegistry {
+    pub fn on<T: 'static>(&mut self, handler: impl FnMut(&T, &EventContext<'_>) + Send + 'static) {
+        self.handlers
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(Vec::<Handler<T>>::new()))
+            .downcast_mut::<Vec<Handler<T>>>()
+            .expect("Handlers are stored under their own event type")
+            .push(Box::new(handler));
+    }
+
+    pub fn has<T: 'static>(&self) -> bool {
+        self.handlers.contains_key(&TypeId::of::<T>())
+    }
+
+    pub fn dispatch<T: 'static>(&mut self, event: &T, ctx: &EventContext<'_>) {
+        if let Some(handlers) = self.handlers.get_mut(&TypeId::of::<T>()) {
+            let handlers = handlers
+                .downcast_mut::<Vec<Handler<T>>>()
+                .expect("Handlers are stored under their own event type");
+            for handler in handlers.iter_mut() {
+                handler(event, ctx);
+            }
+        }
+    }
+}
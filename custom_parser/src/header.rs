@@ -2,7 +2,13 @@ use std::io::{Read, Seek};
 
 use custom_bitreader::BitReader;
 
+use crate::ParseError;
+
 const MAX_OS_PATH: usize = 260;
+pub(crate) const HEADER_LENGTH: usize = 8 + 4 + 4 + (MAX_OS_PATH << 2) + 4 + 4 + 4 + 4;
+
+const DEMO_TYPE: &str = "HL2DEMO";
+const DEMO_PROTOCOL: u32 = 4;
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Header {
@@ -35,6 +41,24 @@ impl Header {
             signon: r.read_signed_int(32) as u32,
         }
     }
+
+    pub fn validate(&self) -> Result<(), ParseError> {
+        if self.demo_type != DEMO_TYPE {
+            return Err(ParseError::InvalidHeader(format!(
+                "expected demo type {DEMO_TYPE}, got {:?}",
+                self.demo_type
+            )));
+        }
+
+        if self.version != DEMO_PROTOCOL {
+            return Err(ParseError::InvalidHeader(format!(
+                "unsupported demo protocol {}, expected {DEMO_PROTOCOL}",
+                self.version
+            )));
+        }
+
+        Ok(())
+    }
 }
 
 impl Default for Header {
@@ -54,3 +78,55 @@ impl Default for Header {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use super::*;
+    use crate::read_header;
+
+    fn header_bytes(demo_type: &[u8], version: u32) -> Vec<u8> {
+        let mut bytes = vec![0; HEADER_LENGTH];
+        bytes[..demo_type.len()].copy_from_slice(demo_type);
+        bytes[8..12].copy_from_slice(&version.to_le_bytes());
+        bytes[12..16].copy_from_slice(&13546_u32.to_le_bytes());
+        bytes[16..26].copy_from_slice(b"GOTV Relay");
+        bytes[16 + 2 * MAX_OS_PATH..16 + 2 * MAX_OS_PATH + 10].copy_from_slice(b"de_dust2\0\0");
+        bytes
+    }
+
+    fn write_demo(name: &str, bytes: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("header-{}-{name}.dem", std::process::id()));
+        fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    fn read(name: &str, bytes: &[u8]) -> Result<Header, ParseError> {
+        let path = write_demo(name, bytes);
+        let header = read_header(&path);
+        fs::remove_file(path).unwrap();
+        header
+    }
+
+    #[test]
+    fn read_header_of_a_valid_demo() {
+        let header = read("valid", &header_bytes(b"HL2DEMO", DEMO_PROTOCOL)).unwrap();
+        assert_eq!(header.demo_type, DEMO_TYPE);
+        assert_eq!(header.protocol, 13546);
+        assert_eq!(header.server, "GOTV Relay");
+        assert_eq!(header.map, "de_dust2");
+    }
+
+    #[test]
+    fn read_header_rejects_other_files() {
+        let err = read("type", &header_bytes(b"HL2DEMX", DEMO_PROTOCOL)).unwrap_err();
+        assert!(matches!(err, ParseError::InvalidHeader(reason) if reason.contains("demo type")));
+
+        let err = read("protocol", &header_bytes(b"HL2DEMO", 5)).unwrap_err();
+        assert!(matches!(err, ParseError::InvalidHeader(reason) if reason.contains("protocol 5")));
+
+        let err = read("short", &header_bytes(b"HL2DEMO", DEMO_PROTOCOL)[..100]).unwrap_err();
+        assert!(matches!(err, ParseError::InvalidHeader(reason) if reason.contains("shorter")));
+    }
+}
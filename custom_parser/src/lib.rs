@@ -1,50 +1,79 @@
-mod commands;
+#[cfg(feature = "async")]
+pub mod async_parser;
+pub mod commands;
 pub mod common;
 pub mod convar;
 mod entity;
 mod equipment;
+pub mod error;
 pub mod events;
 pub mod gameevent;
+pub mod handlers;
 mod header;
 mod player;
 mod sendtable;
 mod serializers;
 pub mod serverclass;
 pub mod serverinfo;
+pub mod snapshot;
+pub mod sound;
+pub mod source;
 mod stringtable;
 mod team;
-mod tick;
+pub mod tempentity;
+pub mod tick;
+pub mod usercmd;
+pub mod voice;
 
 use std::{
     borrow::Cow,
-    fs::File,
-    io::{Cursor, Read, Seek},
+    io::{Cursor, ErrorKind, Read, Seek},
+    panic::{self, AssertUnwindSafe},
     path::Path,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
 };
 
 use common::Vector64;
-use serde_json::json;
-use tokio::sync::Mutex;
+use parking_lot::Mutex;
+use serde::Serialize;
 
 use ahash::AHashMap as HashMap;
-use commands::{MessageType, PacketCommand};
+#[cfg(feature = "async")]
+pub use async_parser::AsyncParser;
+pub use commands::{ConsoleCommand, Frame, FrameMessage, MessageType, PacketCommand};
 use convar::CnetMsgSetConVar;
-use custom_bitreader::BitReader;
-use custom_dispatcher::EventEmitter as AsyncDispatcher;
-use entity::{CsvcMsgPacketEntities, Entity};
+use custom_bitreader::{BitReader, ForwardReader};
+pub use custom_dispatcher::Delivery;
+use custom_dispatcher::EventEmitter as Dispatcher;
+pub use entity::{CsvcMsgPacketEntities, Entity, Property};
+pub use equipment::Equipment;
 use equipment::{
-    bind_weapons, map_equipment, Equipment, GrenadeProjectile, _add_thrown_grenade,
-    get_player_weapon, EQ_DECOY, EQ_FLASH, EQ_HE, EQ_INCENDIARY, EQ_MOLOTOV, EQ_SMOKE,
+    _add_thrown_grenade, bind_weapons, get_player_weapon, map_equipment, GrenadeProjectile,
+    EQ_DECOY, EQ_FLASH, EQ_HE, EQ_INCENDIARY, EQ_MOLOTOV, EQ_SMOKE,
 };
+pub use error::ParseError;
 use events::{
-    BombPlanted, FlashbangDetonate, PlayerDeath, PlayerFootstep, PlayerHurt, PlayerSpawn, RoundEnd,
-    RoundStart, SmokeGrenadeDetonate, SmokeGrenadeExpired, WeaponFire,
+    AmmoPickup, BombAbortDefuse, BombAbortPlant, BombBeginDefuse, BombBeginPlant, BombDefused,
+    BombDropped, BombExploded, BombPickup, BombPlanted, BotTakeover, BulletImpact, CsWinPanelMatch,
+    CsWinPanelRound, DecoyDetonate, DecoyFiring, DecoyStarted, DoorMoving, EnterBombzone,
+    EnterBuyzone, ExitBombzone, ExitBuyzone, FlashbangDetonate, GrenadeBounce, GrenadeThrown,
+    HeGrenadeDetonate, InfernoExpire, InfernoExtinguish, InfernoStartBurn, InspectWeapon,
+    ItemEquip, ItemPickup, ItemPurchase, ItemRemove, MatchEndConditions, MolotovDetonate,
+    OtherDeath, PlayerBlind, PlayerChangeName, PlayerConnect, PlayerConnectFull, PlayerDeath,
+    PlayerDeathEnriched, PlayerDisconnect, PlayerFallDamage, PlayerFootstep, PlayerGivenC4,
+    PlayerHurt, PlayerHurtEnriched, PlayerJump, PlayerSpawn, PlayerTeam, RoundEnd, RoundMvp,
+    RoundStart, SilencerDetach, SmokeGrenadeDetonate, SmokeGrenadeExpired, TaGrenadeDetonate,
+    WeaponFire, WeaponFireEnriched, WeaponFireOnEmpty, WeaponOutOfAmmo, WeaponReload, WeaponZoom,
 };
-use gameevent::{CsvcMsgGameEvent, CsvcMsgGameEventList, GameEvent};
-use header::Header;
-use lazy_static::lazy_static;
-use player::{bind_players, Player, PlayerInfo};
+use gameevent::{CsvcMsgGameEvent, CsvcMsgGameEventList, GameEvent, UntypedGameEvent};
+pub use handlers::{EventContext, HandlerRegistry};
+pub use header::Header;
+use header::HEADER_LENGTH;
+pub use player::Player;
+use player::{bind_players, resolve_event_player, PlayerInfo};
 use prost::Message;
 use sendtable::{
     CsvcMsgSendTable, ExcludeEntry, SendPropertyFlags, SendPropertyFlagsTrait, SendTable,
@@ -52,8 +81,19 @@ use sendtable::{
 };
 use serverclass::{FlattenedPropEntry, ServerClass};
 use serverinfo::CsvcMsgServerInfo;
-use stringtable::{CsvcMsgCreateStringTable, CsvcMsgUpdateStringTable};
-use team::bind_team_states;
+pub use snapshot::{Snapshot, SnapshotInterval};
+pub use sound::Sound;
+use sound::{read_sounds, CsvcMsgSounds};
+pub use source::{Compression, DemoSource};
+pub use stringtable::{CsvcMsgCreateStringTable, CsvcMsgUpdateStringTable};
+use team::{bind_team_states, TeamState, TEAM_COUNTER_TERRORISTS, TEAM_TERRORISTS};
+use tempentity::read_temp_entities;
+pub use tempentity::TempEntity;
+use tick::CnetMsgTick;
+pub use tick::{ServerTiming, ServerTimingStats};
+pub use usercmd::UserCmd;
+use voice::{CsvcMsgVoiceData, CsvcMsgVoiceInit};
+pub use voice::{Speaker, VoiceDecoder, VoicePacket, VoiceRecorder, VoiceTrack};
 
 use crate::{equipment::_retrieve_potential_thrower_owner, serverclass::PropertyValueEnum};
 
@@ -93,484 +133,1347 @@ const N_USER_DATA_BITS: usize = 14;
 
 const ST_NAME_INSTANCE_BASELINE: &str = "instancebaseline";
 const ST_NAME_MODEL_PRECACHE: &str = "modelprecache";
+const ST_NAME_SOUND_PRECACHE: &str = "soundprecache";
 const ST_NAME_USER_INFO: &str = "userinfo";
 
+pub const GAME_EVENT_CHANNEL: &str = "game_event";
+pub const TEMP_ENTITY_CHANNEL: &str = "temp_entity";
+pub const USER_CMD_CHANNEL: &str = "user_cmd";
+pub const CONSOLE_COMMAND_CHANNEL: &str = "console_command";
+pub const SERVER_TIMING_CHANNEL: &str = "server_timing";
+pub const VOICE_DATA_CHANNEL: &str = "voice_data";
+pub const SOUND_CHANNEL: &str = "sound";
+
 pub const PLAYER_NAME_MAX_LENGTH: usize = 128;
 pub const GUID_LENGTH: usize = 33;
 
-lazy_static! {
-    // Globally available caches
-    pub(crate) static ref BYTES_VEC: Mutex<Vec<u8>> = Mutex::new(vec![0_u8; BYTES_VEC_MAX]);
-    pub(crate) static ref PROP_INDICES_VEC: Mutex<Vec<u32>> = Mutex::new(Vec::with_capacity(PROP_INDICES_VEC_CAP));
-    pub(crate) static ref USER_DATA_VEC: Mutex<Vec<u8>> = Mutex::new(Vec::with_capacity(USER_DATA_VEC_CAP));
-    pub(crate) static ref ENTRY_STRING: Mutex<String> = Mutex::new(String::with_capacity(ENTRY_STRING_CAP));
-    pub(crate) static ref HIST_VEC: Mutex<Vec<String>> = Mutex::new(Vec::with_capacity(THIRTY_TWO));
+// Current entry string and user data of each tracked string table, by entry index.
+type StringTableEntries = HashMap<String, HashMap<i32, (String, Vec<u8>)>>;
+
+pub struct ParserState {
+    // Reusable caches
+    pub(crate) bytes_vec: Mutex<Vec<u8>>,
+    pub(crate) prop_indices_vec: Mutex<Vec<u32>>,
+    pub(crate) user_data_vec: Mutex<Vec<u8>>,
+    pub(crate) entry_string: Mutex<String>,
+    pub(crate) hist_vec: Mutex<Vec<String>>,
+
+    send_tables: Mutex<Vec<SendTable>>,
+    send_tables_by_name: Mutex<HashMap<String, SendTable>>,
+    server_classes: Mutex<Vec<ServerClass>>,
+    server_classes_by_name: Mutex<HashMap<String, ServerClass>>,
+    instance_baselines: Mutex<HashMap<i32, Vec<u8>>>,
+    server_class_bits: Mutex<i32>,
+
+    string_tables: Mutex<Vec<CsvcMsgCreateStringTable>>,
+    string_table_entries: Mutex<StringTableEntries>,
 
-    static ref SEND_TABLES: Mutex<Vec<SendTable>> = Mutex::new(Vec::with_capacity(SEND_TABLES_MIN));
-    static ref SEND_TABLES_BY_NAME: Mutex<HashMap<String, SendTable>> = Mutex::new(HashMap::with_capacity(SEND_TABLES_MIN));
-    static ref SERVER_CLASSES: Mutex<Vec<ServerClass>> = Mutex::new(Vec::with_capacity(SERVER_CLASSES_MIN));
-    static ref SERVER_CLASSES_BY_NAME: Mutex<HashMap<String, ServerClass>> = Mutex::new(HashMap::with_capacity(SERVER_CLASSES_MIN));
-    static ref INSTANCE_BASELINES: Mutex<HashMap<i32, Vec<u8>>> = Mutex::new(HashMap::with_capacity(INSTANCE_BASELINES_MIN));
-    static ref SERVER_CLASS_BITS: Mutex<i32> = Mutex::new(0_i32);
+    pub entities: Mutex<HashMap<i32, Entity>>,
 
-    static ref STRING_TABLES: Mutex<Vec<CsvcMsgCreateStringTable>> = Mutex::new(Vec::with_capacity(STRING_TABLES_MIN));
+    pub player_info_by_user_id: Mutex<HashMap<i32, PlayerInfo>>,
+    pub raw_players: Mutex<HashMap<i32, PlayerInfo>>,
 
-    pub static ref ENTITIES: Arc<Mutex<HashMap<i32, Entity>>> = Arc::new(Mutex::new(HashMap::with_capacity(ENTITIES_MIN)));
+    pub players_by_entity_id: Mutex<HashMap<i32, Player>>,
+    pub players_by_user_id: Mutex<HashMap<u32, Player>>,
+    players_by_steam_id: Mutex<HashMap<u64, Player>>,
 
-    pub static ref PLAYER_INFO_BY_USER_ID: Arc<Mutex<HashMap<i32, PlayerInfo>>> = Arc::new(Mutex::new(HashMap::with_capacity(16)));
-    pub static ref RAW_PLAYERS: Arc<Mutex<HashMap<i32, PlayerInfo>>> = Arc::new(Mutex::new(HashMap::with_capacity(16)));
+    pub grenade_projectiles: Mutex<HashMap<i32, GrenadeProjectile>>,
+    pub ingame_tick: Mutex<isize>,
 
-    pub static ref PLAYERS_BY_ENTITY_ID: Arc<Mutex<HashMap<i32, Player>>> = Arc::new(Mutex::new(HashMap::with_capacity(16)));
-    pub static ref PLAYERS_BY_USER_ID: Arc<Mutex<HashMap<u32, Player>>> = Arc::new(Mutex::new(HashMap::with_capacity(16)));
-    static ref PLAYERS_BY_STEAM_ID: Arc<Mutex<HashMap<u64, Player>>> = Arc::new(Mutex::new(HashMap::with_capacity(16)));
+    model_precache: Mutex<Vec<String>>,
+    pub grenade_model_indices: Mutex<HashMap<i32, i32>>,
+    pub thrown_grenades: Mutex<HashMap<i32, Vec<Equipment>>>,
+    // Weapon entities by entity id.
+    pub weapons: Mutex<HashMap<i32, Equipment>>,
+    pub equipment_mapping: Mutex<HashMap<i32, i32>>,
 
-    pub static ref GRENADE_PROJECTILES: Arc<Mutex<HashMap<i32, GrenadeProjectile>>> = Arc::new(Mutex::new(HashMap::new()));
-    pub static ref INGAME_TICK: Arc<Mutex<isize>> = Arc::new(Mutex::new(-1));
+    pub t_state: Mutex<TeamState>,
+    pub ct_state: Mutex<TeamState>,
 
-    static ref MODEL_PRECACHE: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
-    pub static ref GRENADE_MODEL_INDICES: Arc<Mutex<HashMap<i32, i32>>> = Arc::new(Mutex::new(HashMap::new()));
-    pub static ref THROWN_GRENADES: Arc<Mutex<HashMap<i32, Vec<Equipment>>>> = Arc::new(Mutex::new(HashMap::new()));
-    static ref DELAYED_EVENTS: Mutex<Vec<GameEvent>> = Mutex::new(Vec::new());
+    agg: Mutex<u128>,
 
-    static ref AGG: Mutex<u128> = Mutex::new(0);
+    pub server_convars: Mutex<HashMap<String, String>>,
+    pub server_info: Mutex<CsvcMsgServerInfo>,
+    pub tickrate: Mutex<f32>,
 
-    pub static ref SERVER_CONVARS: Mutex<HashMap<String, String>> = Mutex::new(HashMap::new());
-    pub static ref SERVER_INFO: Mutex<CsvcMsgServerInfo> = Mutex::new(CsvcMsgServerInfo::default());
-    pub static ref TICKRATE: Mutex<f32> = Mutex::new(0_f32);
+    pub header: Mutex<Header>,
 
-    pub static ref HEADER: Mutex<Header> = Mutex::new(Header::default());
+    pub dispatcher: Mutex<Dispatcher>,
+    pub player_info_by_steam_id: Mutex<HashMap<u64, PlayerInfo>>,
 
-    pub static ref GLOBAL_DISPATCHER: Mutex<AsyncDispatcher> = Mutex::new(AsyncDispatcher::new());
-    pub static ref PLAYER_INFO_BY_STEAM_ID: Mutex<HashMap<u64, PlayerInfo>> = Mutex::new(HashMap::with_capacity(16));
+    delayed_events: Mutex<Vec<CsvcMsgGameEvent>>,
 }
 
-pub struct Parser {
-    bitreader: BitReader<Cursor<Cow<'static, [u8]>>>,
+impl ParserState {
+    pub(crate) fn emit<T: Serialize>(&self, event: &str, value: T) {
+        self.dispatcher.lock().emit(event, value);
+    }
+}
 
-    pub ingame_tick: isize,
+impl Default for ParserState {
+    fn default() -> Self {
+        Self {
+            bytes_vec: Mutex::new(vec![0_u8; BYTES_VEC_MAX]),
+            prop_indices_vec: Mutex::new(Vec::with_capacity(PROP_INDICES_VEC_CAP)),
+            user_data_vec: Mutex::new(Vec::with_capacity(USER_DATA_VEC_CAP)),
+            entry_string: Mutex::new(String::with_capacity(ENTRY_STRING_CAP)),
+            hist_vec: Mutex::new(Vec::with_capacity(THIRTY_TWO)),
+
+            send_tables: Mutex::new(Vec::with_capacity(SEND_TABLES_MIN)),
+            send_tables_by_name: Mutex::new(HashMap::with_capacity(SEND_TABLES_MIN)),
+            server_classes: Mutex::new(Vec::with_capacity(SERVER_CLASSES_MIN)),
+            server_classes_by_name: Mutex::new(HashMap::with_capacity(SERVER_CLASSES_MIN)),
+            instance_baselines: Mutex::new(HashMap::with_capacity(INSTANCE_BASELINES_MIN)),
+            server_class_bits: Mutex::new(0_i32),
+
+            string_tables: Mutex::new(Vec::with_capacity(STRING_TABLES_MIN)),
+            string_table_entries: Mutex::new(HashMap::new()),
+
+            entities: Mutex::new(HashMap::with_capacity(ENTITIES_MIN)),
+
+            player_info_by_user_id: Mutex::new(HashMap::with_capacity(16)),
+            raw_players: Mutex::new(HashMap::with_capacity(16)),
+
+            players_by_entity_id: Mutex::new(HashMap::with_capacity(16)),
+            players_by_user_id: Mutex::new(HashMap::with_capacity(16)),
+            players_by_steam_id: Mutex::new(HashMap::with_capacity(16)),
+
+            grenade_projectiles: Mutex::new(HashMap::new()),
+            ingame_tick: Mutex::new(-1),
+
+            model_precache: Mutex::new(Vec::new()),
+            grenade_model_indices: Mutex::new(HashMap::new()),
+            thrown_grenades: Mutex::new(HashMap::new()),
+            weapons: Mutex::new(HashMap::new()),
+            equipment_mapping: Mutex::new(HashMap::new()),
+
+            t_state: Mutex::new(TeamState::new(TEAM_TERRORISTS)),
+            ct_state: Mutex::new(TeamState::new(TEAM_COUNTER_TERRORISTS)),
+
+            agg: Mutex::new(0),
+
+            server_convars: Mutex::new(HashMap::new()),
+            server_info: Mutex::new(CsvcMsgServerInfo::default()),
+            tickrate: Mutex::new(0_f32),
+
+            header: Mutex::new(Header::default()),
+
+            dispatcher: Mutex::new(Dispatcher::new()),
+            player_info_by_steam_id: Mutex::new(HashMap::with_capacity(16)),
+
+            delayed_events: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+pub struct Parser<R = DemoSource>
+where
+    R: Read + Seek + Send,
+{
+    bitreader: BitReader<R>,
+
+    pub state: Arc<ParserState>,
 
-    pub dispatcher: AsyncDispatcher,
+    pub ingame_tick: isize,
+    pub last_good_tick: isize,
+    pub truncated: bool,
+    // Game events whose keys didn't have the types their struct expects. They were still
+    // emitted untyped on `game_event`; parsing went on.
+    pub event_errors: Vec<ParseError>,
+    pub current_round: i32,
+    finished: bool,
+    fast_forwarding: bool,
+    last_command: Option<PacketCommand>,
+    delay_game_events: bool,
+    collecting_frames: bool,
+    frame_messages: Vec<FrameMessage>,
+
+    pub snapshots: Vec<Snapshot>,
+    snapshot_interval: Option<SnapshotInterval>,
+    last_snapshot_tick: isize,
+    round_started: bool,
+
+    pub timing_stats: ServerTimingStats,
+    voice_codec: Option<String>,
+    voice: Option<VoiceRecorder>,
+
+    pub dispatcher: Dispatcher,
+    pub handlers: HandlerRegistry,
     pub game_event_list: HashMap<i32, GameEvent>,
 
     pub agg: u128,
 }
 
-impl Parser {
-    pub async fn new_from_file<P: AsRef<Path>>(
-        path: P,
-    ) -> Result<Self, Box<dyn std::error::Error>> {
-        let mut f = File::open(path)?;
-        let mut buf = vec![0; f.metadata()?.len() as usize];
-        f.read_exact(&mut buf)?;
+impl Parser<DemoSource> {
+    // Plain demos as well as .dem.gz, .dem.bz2, .dem.zst and single-demo .zip archives,
+    // recognised by their magic bytes and decompressed while parsing.
+    pub fn new_from_file<P: AsRef<Path>>(path: P) -> Result<Self, ParseError> {
+        Parser::new(DemoSource::open(path)?)
+    }
 
-        let mut bitreader = BitReader::new_large_bit_reader(Cursor::new(Cow::Owned(buf)));
+    // Parses every demo to the end, on at most `concurrency` threads at once. `setup` runs on
+    // each parser before it starts, to register its listeners and handlers. Results are
+    // returned in the same order as `paths`; a demo whose parse panics gets a
+    // `ParseError::Panicked`.
+    pub fn parse_many<I, P, F>(
+        paths: I,
+        concurrency: usize,
+        setup: F,
+    ) -> Vec<Result<Self, ParseError>>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path> + Sync,
+        F: FnMut(&mut Self) + Send,
+    {
+        let paths: Vec<P> = paths.into_iter().collect();
+        let results: Vec<Mutex<Option<Result<Self, ParseError>>>> =
+            paths.iter().map(|_| Mutex::new(None)).collect();
+        let next = AtomicUsize::new(0);
+        let setup = Mutex::new(setup);
+
+        std::thread::scope(|scope| {
+            for _ in 0..concurrency.clamp(1, paths.len().max(1)) {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(path) = paths.get(index) else {
+                        break;
+                    };
+
+                    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                        let mut parser = Parser::new_from_file(path)?;
+                        (setup.lock())(&mut parser);
+                        parser.parse_to_end()?;
+                        Ok(parser)
+                    }))
+                    .unwrap_or_else(|payload| Err(ParseError::panicked(payload)));
+                    *results[index].lock() = Some(result);
+                });
+            }
+        });
+
+        results
+            .into_iter()
+            .map(|result| result.into_inner().expect("Every demo has been parsed"))
+            .collect()
+    }
+}
+
+impl<R: Read + Send> Parser<ForwardReader<R>> {
+    // For sources that can't seek, like pipes and decompressors. Nothing is buffered beyond
+    // the bit reader's own window.
+    pub fn from_reader(reader: R) -> Result<Self, ParseError> {
+        Parser::new(ForwardReader::new(reader))
+    }
+}
+
+impl<R> Parser<R>
+where
+    R: Read + Seek + Send,
+{
+    pub fn new(reader: R) -> Result<Self, ParseError> {
+        let mut bitreader = BitReader::new_large_bit_reader(reader);
+
+        let header = Header::_parse(&mut bitreader);
+        if bitreader.overrun() {
+            if let Some(e) = bitreader.take_error() {
+                return Err(ParseError::Io(e));
+            }
+        }
+        header.validate()?;
 
-        *HEADER.lock().await = Header::_parse(&mut bitreader);
+        let state = Arc::new(ParserState::default());
+        *state.header.lock() = header;
         Ok(Parser {
             bitreader,
+            state,
             ingame_tick: -1,
-            dispatcher: AsyncDispatcher::new(),
+            last_good_tick: -1,
+            truncated: false,
+            event_errors: Vec::new(),
+            current_round: 0,
+            finished: false,
+            fast_forwarding: false,
+            last_command: None,
+            delay_game_events: false,
+            collecting_frames: false,
+            frame_messages: Vec::new(),
+            snapshots: Vec::new(),
+            snapshot_interval: None,
+            last_snapshot_tick: -1,
+            round_started: false,
+            timing_stats: ServerTimingStats::default(),
+            voice_codec: None,
+            voice: None,
+            dispatcher: Dispatcher::new(),
+            handlers: HandlerRegistry::default(),
             game_event_list: HashMap::with_capacity(GAME_EVENT_LIST_MIN),
             agg: 0,
         })
     }
 
-    pub async fn parse_to_end(&mut self) {
-        loop {
-            let command = PacketCommand::from(self.bitreader.read_single_byte());
-            let tick = self.bitreader.read_signed_int(THIRTY_TWO);
-            self.bitreader.skip(ONE_BYTE);
+    pub fn parse_to_end(&mut self) -> Result<(), ParseError> {
+        while self.step()? {}
 
-            self.ingame_tick = tick;
-            *INGAME_TICK.lock().await = tick;
-            match command {
-                PacketCommand::Signon | PacketCommand::Packet => {
-                    self.bitreader.skip(N_COMMAND_INFO_BITS);
+        self.agg = *self.state.agg.lock();
+        Ok(())
+    }
 
-                    let begin_chunk = self.bitreader.read_signed_int(THIRTY_TWO) as usize;
-                    self.bitreader.begin_chunk(begin_chunk << BEGIN_CHUNK_SHIFT);
+    // Parses the next frame and hands it out with the messages it carried, so the state
+    // (`state.entities` and friends) can be inspected between frames. Returns None once the
+    // demo has ended.
+    pub fn next_frame(&mut self) -> Result<Option<Frame>, ParseError> {
+        self.frame_messages.clear();
+        self.collecting_frames = true;
+        let parsed = self.step();
+        self.collecting_frames = false;
+
+        match (parsed?, self.last_command) {
+            (true, Some(command)) => Ok(Some(Frame {
+                command,
+                tick: self.ingame_tick,
+                messages: std::mem::take(&mut self.frame_messages),
+            })),
+            _ => Ok(None),
+        }
+    }
 
-                    let mut bytes_vec = BYTES_VEC.lock().await;
-                    while !self.bitreader.chunk_finished() {
-                        let cmd = MessageType::from(self.bitreader.read_varint32());
-                        let size = self.bitreader.read_varint32() as usize;
+    // Fast-forwards until the frame at or after `tick` has been applied. Entity and string
+    // table state is kept up to date, but no events reach the listeners on the way. Returns
+    // false if the demo ended first.
+    pub fn seek_to_tick(&mut self, tick: isize) -> Result<bool, ParseError> {
+        self.fast_forward(|parser| parser.ingame_tick >= tick)
+    }
 
-                        self.bitreader.begin_chunk(size << BEGIN_CHUNK_SHIFT);
+    // Like `seek_to_tick`, stopping on the frame that starts round `round` (1 based, counted
+    // from the match start).
+    pub fn seek_to_round(&mut self, round: i32) -> Result<bool, ParseError> {
+        self.fast_forward(|parser| parser.current_round >= round)
+    }
 
-                        if cmd.is_skippable() {
-                            self.bitreader.end_chunk();
-                            continue;
-                        }
+    fn fast_forward<F>(&mut self, done: F) -> Result<bool, ParseError>
+    where
+        F: Fn(&Self) -> bool,
+    {
+        let listeners = std::mem::take(&mut self.dispatcher);
+        let state_listeners = std::mem::take(&mut *self.state.dispatcher.lock());
+        self.fast_forwarding = true;
+
+        let mut reached = done(self);
+        let mut result = Ok(());
+        while !reached {
+            match self.step() {
+                Ok(true) => reached = done(self),
+                Ok(false) => break,
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            }
+        }
 
-                        self.bitreader.read_bytes_into(&mut bytes_vec, size);
+        self.fast_forwarding = false;
+        self.dispatcher = listeners;
+        *self.state.dispatcher.lock() = state_listeners;
 
-                        match cmd {
-                            MessageType::SetConVar => {
-                                self.handle_set_convars(&bytes_vec[0..size]).await
-                            }
-                            MessageType::ServerInfo => {
-                                let msg = CsvcMsgServerInfo::decode(&bytes_vec[0..size])
-                                    .expect("Failed to decode CsvcMsgServerInfo.");
-                                let tickrate = 1.0 / msg.tick_interval();
-                                if tickrate == 0_f32 {
-                                    *TICKRATE.lock().await = 128.0;
-                                } else {
-                                    *TICKRATE.lock().await = tickrate.round();
-                                }
-                                *SERVER_INFO.lock().await = msg;
-                            }
-                            MessageType::CreateStringTable => {
-                                handle_create_string_table(&bytes_vec[0..size]).await
-                            }
-                            MessageType::UpdateStringTable => {
-                                handle_update_string_table(&bytes_vec[0..size]).await
-                            }
-                            MessageType::GameEvent => {
-                                self.handle_game_event(&bytes_vec[0..size]).await
+        result.map(|_| reached)
+    }
+
+    // Resumes a demo from a snapshot taken by an earlier parse of the same demo. Only the
+    // signon data is decoded again, the rest of the state comes from the snapshot.
+    pub fn resume(reader: R, snapshot: &Snapshot) -> Result<Self, ParseError> {
+        let mut parser = Parser::new(reader)?;
+
+        let signon_end = (HEADER_LENGTH + parser.state.header.lock().signon as usize) << 3;
+        parser.fast_forward(|parser| parser.bitreader.actual_position() >= signon_end)?;
+        parser.restore(snapshot)?;
+
+        Ok(parser)
+    }
+
+    // Calls `handler` with every `T` event, synchronously and in registration order, e.g.
+    // `parser.on::<PlayerDeath>(|e, ctx| ...)`.
+    pub fn on<T: 'static>(&mut self, handler: impl FnMut(&T, &EventContext<'_>) + Send + 'static) {
+        self.handlers.on(handler);
+    }
+
+    // How async dispatcher listeners are run, for the parser's own dispatcher as well as the
+    // one in `state`. `Delivery::Ordered` runs them in tick order before parsing moves on.
+    pub fn set_delivery(&mut self, delivery: Delivery) {
+        self.dispatcher.delivery = delivery;
+        self.state.dispatcher.lock().delivery = delivery;
+    }
+
+    // Holds game events back until the rest of their frame, entity updates included, has been
+    // applied, so handlers see the entities as they are after the event rather than before.
+    pub fn delay_game_events(&mut self, delay: bool) {
+        self.delay_game_events = delay;
+    }
+
+    // Keeps a snapshot every `interval` in `snapshots` while parsing.
+    pub fn record_snapshots(&mut self, interval: SnapshotInterval) {
+        self.snapshot_interval = Some(interval);
+    }
+
+    // Collects voice data per speaker from here on, for export through `voice()`.
+    pub fn record_voice(&mut self) {
+        self.voice.get_or_insert_with(|| VoiceRecorder {
+            codec: self.voice_codec.to_owned(),
+            ..VoiceRecorder::default()
+        });
+    }
+
+    pub fn voice(&self) -> Option<&VoiceRecorder> {
+        self.voice.as_ref()
+    }
+
+    // Snapshot of the current state. Only meaningful between frames.
+    pub fn snapshot(&mut self) -> Snapshot {
+        let mut string_table_entries = Vec::new();
+        for (table, entries) in self.state.string_table_entries.lock().iter() {
+            for (index, (entry, user_data)) in entries {
+                string_table_entries.push(snapshot::StringTableEntry {
+                    table: table.to_owned(),
+                    index: *index,
+                    entry: entry.to_owned(),
+                    user_data: user_data.to_owned(),
+                });
+            }
+        }
+
+        let mut entities: Vec<_> = self
+            .state
+            .entities
+            .lock()
+            .values()
+            .map(|entity| snapshot::EntitySnapshot {
+                id: entity.id,
+                server_class_index: entity.server_class.index,
+                created_on_tick: entity.created_on_tick,
+                props: entity
+                    .props
+                    .iter()
+                    .map(|prop| prop.value.to_owned())
+                    .collect(),
+                team: entity.team,
+                last_position: entity.last_position.to_owned(),
+                is_in_buyzone: entity.is_in_buyzone,
+                buyzone_leave_pos: entity.buyzone_leave_pos.to_owned(),
+                last_flash_duration: entity.last_flash_duration,
+                current_flash_frame_agg: entity.current_flash_frame_agg,
+            })
+            .collect();
+        entities.sort_by_key(|entity| entity.id);
+
+        let mut grenade_projectiles: Vec<_> = self
+            .state
+            .grenade_projectiles
+            .lock()
+            .values()
+            .map(|proj| snapshot::GrenadeProjectileSnapshot {
+                entity_id: proj.entity_id,
+                wep_type: proj.wep_type,
+                trajectory: proj.trajectory.to_owned(),
+            })
+            .collect();
+        grenade_projectiles.sort_by_key(|proj| proj.entity_id);
+
+        let mut thrown_grenades: Vec<_> = self
+            .state
+            .thrown_grenades
+            .lock()
+            .iter()
+            .map(|(entity_id, grenades)| (*entity_id, grenades.iter().map(Into::into).collect()))
+            .collect();
+        thrown_grenades.sort_by_key(|(entity_id, _)| *entity_id);
+
+        Snapshot {
+            tick: self.ingame_tick,
+            round: self.current_round,
+            offset: self.bitreader.actual_position() >> 3,
+            string_table_entries,
+            entities,
+            grenade_projectiles,
+            thrown_grenades,
+        }
+    }
+
+    fn restore(&mut self, snapshot: &Snapshot) -> Result<(), ParseError> {
+        let state = Arc::clone(&self.state);
+
+        {
+            let mut model_precache = state.model_precache.lock();
+            let mut server_classes = state.server_classes.lock();
+            for e in &snapshot.string_table_entries {
+                apply_string_table_entry(
+                    &state,
+                    &e.table,
+                    e.index,
+                    &e.entry,
+                    &e.user_data,
+                    &mut model_precache,
+                    &mut server_classes,
+                )?;
+            }
+            index_grenade_models(&state, &model_precache);
+        }
+
+        self.ingame_tick = snapshot.tick;
+        *state.ingame_tick.lock() = snapshot.tick;
+
+        // Players, weapons, projectiles and teams are bound to entities by the created
+        // handlers of their server classes, so they come back with the entities.
+        state.entities.lock().clear();
+        state.players_by_entity_id.lock().clear();
+        state.players_by_user_id.lock().clear();
+        state.players_by_steam_id.lock().clear();
+        state.weapons.lock().clear();
+        state.grenade_projectiles.lock().clear();
+        state.t_state.lock().entity_id = None;
+        state.ct_state.lock().entity_id = None;
+
+        let server_classes = state.server_classes.lock();
+        for es in &snapshot.entities {
+            let entity = match server_classes.get(es.server_class_index as usize) {
+                Some(server_class) => server_class.restore_entity(&state, es)?,
+                None => {
+                    return Err(ParseError::malformed(format!(
+                        "Unknown server class {} for entity {}",
+                        es.server_class_index, es.id
+                    )))
+                }
+            };
+            link_player_info(&state, entity.id);
+            state.entities.lock().insert(es.id, entity);
+        }
+        drop(server_classes);
+
+        // Needs every player back first.
+        for ps in &snapshot.grenade_projectiles {
+            let entities = state.entities.lock();
+            let mut grenade_projectiles = state.grenade_projectiles.lock();
+            if let (Some(proj), Some(entity)) = (
+                grenade_projectiles.get_mut(&ps.entity_id),
+                entities.get(&ps.entity_id),
+            ) {
+                proj.wep_type = ps.wep_type;
+                proj.trajectory = ps.trajectory.to_owned();
+                _retrieve_potential_thrower_owner(&state, entity, proj);
+                proj.weapon_instance = get_player_weapon(&proj.thrower, proj.wep_type);
+            }
+        }
+
+        *state.thrown_grenades.lock() = snapshot
+            .thrown_grenades
+            .iter()
+            .map(|(entity_id, grenades)| (*entity_id, grenades.iter().map(Into::into).collect()))
+            .collect();
+
+        self.bitreader.seek_to(snapshot.offset)?;
+        self.current_round = snapshot.round;
+        self.last_good_tick = snapshot.tick;
+        self.last_snapshot_tick = snapshot.tick;
+
+        Ok(())
+    }
+
+    fn record_due_snapshot(&mut self) {
+        let due = match self.snapshot_interval {
+            Some(SnapshotInterval::Ticks(ticks)) => {
+                self.last_snapshot_tick < 0 || self.ingame_tick - self.last_snapshot_tick >= ticks
+            }
+            Some(SnapshotInterval::RoundStart) => self.round_started,
+            None => false,
+        };
+
+        if due {
+            let snapshot = self.snapshot();
+            self.snapshots.push(snapshot);
+            self.last_snapshot_tick = self.ingame_tick;
+        }
+    }
+
+    // Parses a single frame. Returns false once the demo has ended, either on its Stop
+    // command or because it was cut off (server crash, GOTV still recording). Running out of
+    // data is not an error: `truncated` gets set and `last_good_tick` points at the last frame
+    // that was read in full. Failing to read it is, and comes back as `ParseError::Io`.
+    fn step(&mut self) -> Result<bool, ParseError> {
+        if self.finished {
+            return Ok(false);
+        }
+
+        self.round_started = false;
+        match self.parse_frame() {
+            Ok(true) if !self.bitreader.overrun() => {
+                self.last_good_tick = self.ingame_tick;
+                self.record_due_snapshot();
+                return Ok(true);
+            }
+            Ok(false) => {}
+            Ok(true) | Err(ParseError::Truncated { .. }) => self.end_of_data()?,
+            Err(_) if self.bitreader.overrun() => self.end_of_data()?,
+            Err(e) => return Err(e.at(self.ingame_tick, self.bitreader.actual_position() >> 3)),
+        }
+
+        self.finished = true;
+        Ok(false)
+    }
+
+    fn end_of_data(&mut self) -> Result<(), ParseError> {
+        if let Some(e) = self.bitreader.take_error() {
+            self.finished = true;
+            return Err(ParseError::Io(e));
+        }
+
+        self.truncated = true;
+        Ok(())
+    }
+
+    fn parse_frame(&mut self) -> Result<bool, ParseError> {
+        let state = Arc::clone(&self.state);
+        let command =
+            PacketCommand::try_from(self.bitreader.read_single_byte()).map_err(|command| {
+                ParseError::UnknownCommand {
+                    tick: -1,
+                    offset: 0,
+                    command,
+                }
+            })?;
+        let tick = self.bitreader.read_signed_int(THIRTY_TWO);
+        self.bitreader.skip(ONE_BYTE);
+        self.ensure_remaining(0)?;
+
+        self.ingame_tick = tick;
+        self.last_command = Some(command);
+        *state.ingame_tick.lock() = tick;
+        match command {
+            PacketCommand::Signon | PacketCommand::Packet => {
+                self.bitreader.skip(N_COMMAND_INFO_BITS);
+
+                let begin_chunk = self.bitreader.read_signed_int(THIRTY_TWO) as usize;
+                self.ensure_remaining(begin_chunk << BEGIN_CHUNK_SHIFT)?;
+                self.bitreader.begin_chunk(begin_chunk << BEGIN_CHUNK_SHIFT);
+
+                let mut bytes_vec = state.bytes_vec.lock();
+                while !self.bitreader.chunk_finished() {
+                    let id = self.bitreader.read_varint32();
+                    let cmd = MessageType::from(id);
+                    let size = self.bitreader.read_varint32() as usize;
+
+                    self.bitreader.begin_chunk(size << BEGIN_CHUNK_SHIFT);
+
+                    if cmd.is_skippable() {
+                        self.bitreader.end_chunk()?;
+                        continue;
+                    }
+
+                    self.bitreader.read_bytes_into(&mut bytes_vec, size);
+
+                    if self.collecting_frames {
+                        self.frame_messages
+                            .push(FrameMessage::decode(id, &bytes_vec[0..size])?);
+                    }
+
+                    match cmd {
+                        MessageType::Tick => self.handle_tick(&bytes_vec[0..size])?,
+                        MessageType::SetConVar => self.handle_set_convars(&bytes_vec[0..size])?,
+                        MessageType::ServerInfo => {
+                            let msg =
+                                CsvcMsgServerInfo::decode(&bytes_vec[0..size]).map_err(|e| {
+                                    ParseError::decode("Failed to decode CsvcMsgServerInfo", e)
+                                })?;
+                            let tickrate = 1.0 / msg.tick_interval();
+                            if tickrate == 0_f32 {
+                                *state.tickrate.lock() = 128.0;
+                            } else {
+                                *state.tickrate.lock() = tickrate.round();
                             }
-                            MessageType::PacketEntities => {
-                                self.handle_packet_entities(&bytes_vec[0..size]).await
+                            *state.server_info.lock() = msg;
+                        }
+                        MessageType::CreateStringTable => {
+                            handle_create_string_table(&state, &bytes_vec[0..size])?
+                        }
+                        MessageType::UpdateStringTable => {
+                            handle_update_string_table(&state, &bytes_vec[0..size])?
+                        }
+                        MessageType::GameEvent => {
+                            let msg =
+                                CsvcMsgGameEvent::decode(&bytes_vec[0..size]).map_err(|e| {
+                                    ParseError::decode(
+                                        "Failed to decode bytes into CsvcMsgGameEvent",
+                                        e,
+                                    )
+                                })?;
+
+                            if self.delay_game_events {
+                                state.delayed_events.lock().push(msg);
+                            } else {
+                                self.handle_game_event(msg)?
                             }
-                            MessageType::GameEventList => {
-                                self.handle_game_event_list(&bytes_vec[0..size]).await
+                        }
+                        MessageType::PacketEntities => {
+                            self.handle_packet_entities(&bytes_vec[0..size])?
+                        }
+                        MessageType::VoiceInit => {
+                            let msg =
+                                CsvcMsgVoiceInit::decode(&bytes_vec[0..size]).map_err(|e| {
+                                    ParseError::decode("Failed to decode CsvcMsgVoiceInit", e)
+                                })?;
+                            self.voice_codec = msg.codec;
+                            if let Some(voice) = &mut self.voice {
+                                voice.codec = self.voice_codec.to_owned();
                             }
-                            _ => {}
                         }
-
-                        self.bitreader.end_chunk();
+                        MessageType::VoiceData => self.handle_voice_data(&bytes_vec[0..size])?,
+                        MessageType::Sounds => self.handle_sounds(&bytes_vec[0..size])?,
+                        MessageType::GameEventList => {
+                            self.handle_game_event_list(&bytes_vec[0..size])?
+                        }
+                        MessageType::TempEntities => {
+                            self.handle_temp_entities(&bytes_vec[0..size])?
+                        }
+                        _ => {}
                     }
-                    self.bitreader.end_chunk();
+
+                    self.bitreader.end_chunk()?;
                 }
-                PacketCommand::Sync => {}
-                PacketCommand::Console => {}
-                PacketCommand::User => {}
-                PacketCommand::Data => {
-                    let mut send_tables = SEND_TABLES.lock().await;
-
-                    let begin_chunk = self.bitreader.read_signed_int(THIRTY_TWO) as usize;
-                    self.bitreader.begin_chunk(begin_chunk << BEGIN_CHUNK_SHIFT);
-                    loop {
-                        let t = self.bitreader.read_varint32();
-                        if t != SERVER_CLASS_IDENTIFIER {
-                            panic!("Expected SendTable ({SERVER_CLASS_IDENTIFIER}), got ({t})");
-                        }
+                self.bitreader.end_chunk()?;
+                drop(bytes_vec);
 
-                        let size = self.bitreader.read_varint32() as usize;
-                        self.bitreader.begin_chunk(size << BEGIN_CHUNK_SHIFT);
+                let delayed_events = std::mem::take(&mut *state.delayed_events.lock());
+                for msg in delayed_events {
+                    self.handle_game_event(msg)?;
+                }
+            }
+            PacketCommand::Sync => {}
+            PacketCommand::Console => {
+                let wanted =
+                    !self.fast_forwarding && self.wants::<ConsoleCommand>(CONSOLE_COMMAND_CHANNEL);
+                if let Some(data) = self.read_raw_data(wanted)? {
+                    let command = ConsoleCommand {
+                        tick,
+                        command: String::from_utf8_lossy(&data)
+                            .trim_end_matches('\0')
+                            .to_owned(),
+                    };
+                    self.dispatch(&command);
+                    if self.dispatcher.has_listeners(CONSOLE_COMMAND_CHANNEL) {
+                        self.emit(CONSOLE_COMMAND_CHANNEL, command);
+                    }
+                }
+            }
+            PacketCommand::User => {
+                let outgoing_sequence = self.bitreader.read_signed_int(THIRTY_TWO) as i32;
+
+                let wanted = !self.fast_forwarding && self.wants::<UserCmd>(USER_CMD_CHANNEL);
+                if let Some(data) = self.read_raw_data(wanted)? {
+                    let cmd = UserCmd::decode(tick, outgoing_sequence, &data);
+                    self.dispatch(&cmd);
+                    if self.dispatcher.has_listeners(USER_CMD_CHANNEL) {
+                        self.emit(USER_CMD_CHANNEL, cmd);
+                    }
+                }
+            }
+            PacketCommand::Data => {
+                let mut send_tables = state.send_tables.lock();
+
+                let begin_chunk = self.bitreader.read_signed_int(THIRTY_TWO) as usize;
+                self.ensure_remaining(begin_chunk << BEGIN_CHUNK_SHIFT)?;
+                self.bitreader.begin_chunk(begin_chunk << BEGIN_CHUNK_SHIFT);
+                loop {
+                    let t = self.bitreader.read_varint32();
+                    if t != SERVER_CLASS_IDENTIFIER {
+                        return Err(ParseError::malformed(format!(
+                            "Expected SendTable ({SERVER_CLASS_IDENTIFIER}), got ({t})"
+                        )));
+                    }
 
-                        let st = CsvcMsgSendTable::decode(self.bitreader.read_bytes(size).as_ref())
-                            .expect("Failed to decode CsvcMsgSendTable.");
-                        self.bitreader.end_chunk();
+                    let size = self.bitreader.read_varint32() as usize;
+                    self.bitreader.begin_chunk(size << BEGIN_CHUNK_SHIFT);
 
-                        let mut send_table = SendTable::from(st);
-                        send_table.index = send_tables.len() as i32;
+                    let st = CsvcMsgSendTable::decode(self.bitreader.read_bytes(size).as_ref())
+                        .map_err(|e| ParseError::decode("Failed to decode CsvcMsgSendTable", e))?;
+                    self.bitreader.end_chunk()?;
 
-                        if send_table.is_end {
-                            break;
-                        }
+                    let mut send_table = SendTable::from(st);
+                    send_table.index = send_tables.len() as i32;
 
-                        send_tables.push(send_table.to_owned());
-                        SEND_TABLES_BY_NAME
-                            .lock()
-                            .await
-                            .insert(send_table.name.to_owned(), send_table.to_owned());
+                    if send_table.is_end {
+                        break;
                     }
 
-                    let server_class_count = self.bitreader.read_int(TWO_BYTES);
+                    send_tables.push(send_table.to_owned());
+                    state
+                        .send_tables_by_name
+                        .lock()
+                        .insert(send_table.name.to_owned(), send_table.to_owned());
+                }
 
-                    for i in 0..server_class_count {
-                        let mut server_class =
-                            ServerClass::new(i, &mut self.bitreader, server_class_count);
+                let server_class_count = self.bitreader.read_int(TWO_BYTES);
 
-                        if let Some(instance_baseline) =
-                            INSTANCE_BASELINES.lock().await.get(&(i as i32))
-                        {
-                            server_class.instance_baseline = Some(instance_baseline.to_owned());
-                        }
+                for i in 0..server_class_count {
+                    let mut server_class =
+                        ServerClass::new(i, &mut self.bitreader, server_class_count)?;
 
-                        SERVER_CLASSES.lock().await.push(server_class.to_owned());
-                        SERVER_CLASSES_BY_NAME
-                            .lock()
-                            .await
-                            .insert(server_class.dt_name.to_owned(), server_class.to_owned());
+                    if let Some(instance_baseline) =
+                        state.instance_baselines.lock().get(&(i as i32))
+                    {
+                        server_class.instance_baseline = Some(instance_baseline.to_owned());
                     }
 
-                    for i in 0..server_class_count {
-                        let mut server_classes = SERVER_CLASSES.lock().await;
-                        if let Some(msg) = send_tables.get(server_classes[i].index as usize) {
-                            let mut sc_map: HashMap<String, ServerClass> = HashMap::new();
-                            let mut ex_map: HashMap<String, ExcludeEntry> = HashMap::new();
+                    state.server_classes.lock().push(server_class.to_owned());
+                    state
+                        .server_classes_by_name
+                        .lock()
+                        .insert(server_class.dt_name.to_owned(), server_class.to_owned());
+                }
 
-                            gather_prerequisites(msg, true, &mut sc_map, &mut ex_map).await;
+                for i in 0..server_class_count {
+                    let mut server_classes = state.server_classes.lock();
+                    if let Some(msg) = send_tables.get(server_classes[i].index as usize) {
+                        let mut sc_map: HashMap<String, ServerClass> = HashMap::new();
+                        let mut ex_map: HashMap<String, ExcludeEntry> = HashMap::new();
 
-                            server_classes[i].base_classes_by_name = Some(sc_map.to_owned());
-                            SERVER_CLASSES_BY_NAME
-                                .lock()
-                                .await
-                                .get_mut(&server_classes[i].dt_name)
-                                .unwrap()
-                                .base_classes_by_name = Some(sc_map.to_owned());
+                        gather_prerequisites(&state, msg, true, &mut sc_map, &mut ex_map)?;
 
-                            gather_props(msg, i, "", &mut server_classes, &ex_map).await;
+                        server_classes[i].base_classes_by_name = Some(sc_map.to_owned());
+                        if let Some(server_class) = state
+                            .server_classes_by_name
+                            .lock()
+                            .get_mut(&server_classes[i].dt_name)
+                        {
+                            server_class.base_classes_by_name = Some(sc_map.to_owned());
+                        }
 
-                            if let Some(flattened_props) = &mut server_classes[i].flattened_props {
-                                let mut prio_set: HashMap<i32, i32> =
-                                    HashMap::from([(DEFAULT_PRIO_KEY, DEFAULT_PRIO_VAL)]);
+                        gather_props(&state, msg, i, "", &mut server_classes, &ex_map)?;
 
-                                for fp in flattened_props.iter() {
-                                    prio_set.insert(fp.prop.priority, DEFAULT_PRIO_VAL);
-                                }
+                        if let Some(flattened_props) = &mut server_classes[i].flattened_props {
+                            let mut prio_set: HashMap<i32, i32> =
+                                HashMap::from([(DEFAULT_PRIO_KEY, DEFAULT_PRIO_VAL)]);
 
-                                let mut prios: Vec<i32> = Vec::with_capacity(prio_set.len());
-                                for (prio, _) in prio_set {
-                                    prios.push(prio);
-                                }
-                                prios.sort();
-
-                                let mut start = 0;
-
-                                for prio in prios {
-                                    loop {
-                                        let mut cp = start;
-                                        while cp < flattened_props.len() {
-                                            let prop = &flattened_props[cp].prop;
-                                            if prop.priority == prio
-                                                || (prio == DEFAULT_PRIO_KEY
-                                                    && prop
-                                                        .flags
-                                                        .has_flag_set(PROP_FLAG_CHANGES_OFTEN))
-                                            {
-                                                if start != cp {
-                                                    flattened_props.swap(start, cp);
-                                                }
-
-                                                start += 1;
-                                                break;
+                            for fp in flattened_props.iter() {
+                                prio_set.insert(fp.prop.priority, DEFAULT_PRIO_VAL);
+                            }
+
+                            let mut prios: Vec<i32> = Vec::with_capacity(prio_set.len());
+                            for (prio, _) in prio_set {
+                                prios.push(prio);
+                            }
+                            prios.sort();
+
+                            let mut start = 0;
+
+                            for prio in prios {
+                                loop {
+                                    let mut cp = start;
+                                    while cp < flattened_props.len() {
+                                        let prop = &flattened_props[cp].prop;
+                                        if prop.priority == prio
+                                            || (prio == DEFAULT_PRIO_KEY
+                                                && prop.flags.has_flag_set(PROP_FLAG_CHANGES_OFTEN))
+                                        {
+                                            if start != cp {
+                                                flattened_props.swap(start, cp);
                                             }
-                                            cp += 1;
-                                        }
 
-                                        if cp == flattened_props.len() {
+                                            start += 1;
                                             break;
                                         }
+                                        cp += 1;
+                                    }
+
+                                    if cp == flattened_props.len() {
+                                        break;
                                     }
                                 }
                             }
                         }
                     }
+                }
 
-                    for (_idx, server_class) in SERVER_CLASSES.lock().await.iter_mut().enumerate() {
-                        if let Some(flattened_props) = &server_class.flattened_props {
-                            let mut prop_name_to_idx =
-                                HashMap::with_capacity(flattened_props.len());
-                            for (i, fp) in flattened_props.iter().enumerate() {
-                                prop_name_to_idx.insert(fp.name.to_string(), i as i32);
-                                if let Some(scbn) = SERVER_CLASSES_BY_NAME
-                                    .lock()
-                                    .await
-                                    .get_mut(&server_class.dt_name)
-                                {
-                                    if let Some(scbn_prop_name_to_idx) = &mut scbn.prop_name_to_idx
-                                    {
-                                        scbn_prop_name_to_idx.insert(fp.name.to_string(), i as i32);
-                                    }
+                for server_class in state.server_classes.lock().iter_mut() {
+                    if let Some(flattened_props) = &server_class.flattened_props {
+                        let mut prop_name_to_idx = HashMap::with_capacity(flattened_props.len());
+                        for (i, fp) in flattened_props.iter().enumerate() {
+                            prop_name_to_idx.insert(fp.name.to_string(), i as i32);
+                            if let Some(scbn) = state
+                                .server_classes_by_name
+                                .lock()
+                                .get_mut(&server_class.dt_name)
+                            {
+                                if let Some(scbn_prop_name_to_idx) = &mut scbn.prop_name_to_idx {
+                                    scbn_prop_name_to_idx.insert(fp.name.to_string(), i as i32);
                                 }
                             }
-                            server_class.prop_name_to_idx = Some(prop_name_to_idx);
                         }
+                        server_class.prop_name_to_idx = Some(prop_name_to_idx);
                     }
-                    *SERVER_CLASS_BITS.lock().await =
-                        f32::ceil(f32::log2(SERVER_CLASSES.lock().await.len() as f32)) as i32;
+                }
+                *state.server_class_bits.lock() =
+                    f32::ceil(f32::log2(state.server_classes.lock().len() as f32)) as i32;
 
-                    self.bitreader.end_chunk();
+                self.bitreader.end_chunk()?;
 
-                    map_equipment().await;
-                    bind_entities().await;
+                map_equipment(&state);
+                bind_entities(&state);
+            }
+            PacketCommand::Stop => return Ok(false),
+            PacketCommand::Custom => {
+                // Callback index, then data only the game's own demo player knows about.
+                self.bitreader.skip(THIRTY_TWO);
+                self.read_raw_data(false)?;
+            }
+            PacketCommand::String => {
+                if let Some(data) = self.read_raw_data(true)? {
+                    handle_string_tables_dump(&state, &data)?;
                 }
-                PacketCommand::Stop => break,
-                PacketCommand::Custom => {}
-                PacketCommand::String => {}
             }
+        }
 
-            self.dispatcher.emit("frame_done", tick).await;
-            for (entity_id, _) in PLAYERS_BY_ENTITY_ID.lock().await.iter() {
-                if let Some(entity) = ENTITIES.lock().await.get_mut(entity_id) {
-                    entity
-                        .position_history
-                        .insert(self.ingame_tick, entity.get_position());
+        self.emit("frame_done", tick);
+        for (entity_id, _) in state.players_by_entity_id.lock().iter() {
+            if let Some(entity) = state.entities.lock().get_mut(entity_id) {
+                entity
+                    .position_history
+                    .insert(self.ingame_tick, entity.get_position());
 
-                    if let Some(prop) = entity.property("m_iTeamNum") {
-                        if let PropertyValueEnum::Integer(Cow::Owned(team)) = prop.value {
-                            entity.team = team as u8;
-                        }
+                if let Some(prop) = entity.property("m_iTeamNum") {
+                    if let PropertyValueEnum::Integer(Cow::Owned(team)) = prop.value {
+                        entity.team = team as u8;
                     }
+                }
 
-                    if entity.is_blind() {
-                        entity.current_flash_frame_agg += 1;
-                    }
+                if entity.is_blind() {
+                    entity.current_flash_frame_agg += 1;
                 }
             }
+        }
 
-            for (_, player_thrown_grenades) in THROWN_GRENADES.lock().await.iter() {
-                for thrown_grenade in player_thrown_grenades {
-                    if let (Some(proj), Some(entity)) = (
-                        GRENADE_PROJECTILES
-                            .lock()
-                            .await
-                            .get_mut(&thrown_grenade.entity_id),
-                        ENTITIES.lock().await.get_mut(&thrown_grenade.entity_id),
-                    ) {
-                        let current_position = entity.get_position();
-                        if proj.trajectory.is_empty() {
-                            if current_position != Vector64::default() {
-                                proj.trajectory.push(current_position.to_owned());
-                                entity.last_position = current_position;
-                            }
-                        } else if proj.trajectory[proj.trajectory.len() - 1] != current_position {
+        for (_, player_thrown_grenades) in state.thrown_grenades.lock().iter() {
+            for thrown_grenade in player_thrown_grenades {
+                if let (Some(proj), Some(entity)) = (
+                    state
+                        .grenade_projectiles
+                        .lock()
+                        .get_mut(&thrown_grenade.entity_id),
+                    state.entities.lock().get_mut(&thrown_grenade.entity_id),
+                ) {
+                    let current_position = entity.get_position();
+                    if proj.trajectory.is_empty() {
+                        if current_position != Vector64::default() {
                             proj.trajectory.push(current_position.to_owned());
                             entity.last_position = current_position;
                         }
+                    } else if proj.trajectory[proj.trajectory.len() - 1] != current_position {
+                        proj.trajectory.push(current_position.to_owned());
+                        entity.last_position = current_position;
                     }
                 }
             }
         }
 
-        self.agg = *AGG.lock().await;
+        Ok(true)
     }
 
-    async fn handle_set_convars(&mut self, bytes: &[u8]) {
-        let msg =
-            CnetMsgSetConVar::decode(bytes).expect("Failed to decode bytes into CnetMsgSetConVar.");
+    #[inline]
+    fn ensure_remaining(&self, bits: usize) -> Result<(), ParseError> {
+        if self.bitreader.overrun() || self.bitreader.remaining_bits().is_some_and(|r| r < bits) {
+            return Err(ParseError::Truncated {
+                tick: -1,
+                offset: 0,
+            });
+        }
+
+        Ok(())
+    }
+
+    // A length prefixed blob, as written for the console, user, custom and string table
+    // frames. Only read into memory when `keep` is set.
+    fn read_raw_data(&mut self, keep: bool) -> Result<Option<Vec<u8>>, ParseError> {
+        let size = self.bitreader.read_signed_int(THIRTY_TWO) as usize;
+        self.ensure_remaining(size << BEGIN_CHUNK_SHIFT)?;
+        self.bitreader.begin_chunk(size << BEGIN_CHUNK_SHIFT);
+
+        let data = keep.then(|| self.bitreader.read_bytes(size));
+        self.bitreader.end_chunk()?;
+
+        Ok(data)
+    }
+
+    fn handle_tick(&mut self, bytes: &[u8]) -> Result<(), ParseError> {
+        let msg = CnetMsgTick::decode(bytes)
+            .map_err(|e| ParseError::decode("Failed to decode bytes into CnetMsgTick", e))?;
+
+        let timing = ServerTiming::new(self.ingame_tick, &msg);
+        self.timing_stats.add(&timing);
+
+        if self.fast_forwarding {
+            return Ok(());
+        }
+
+        self.dispatch(&timing);
+        if self.dispatcher.has_listeners(SERVER_TIMING_CHANNEL) {
+            self.emit(SERVER_TIMING_CHANNEL, timing);
+        }
+
+        Ok(())
+    }
+
+    fn handle_voice_data(&mut self, bytes: &[u8]) -> Result<(), ParseError> {
+        let wanted = self.wants::<VoicePacket>(VOICE_DATA_CHANNEL);
+        if self.fast_forwarding || !(wanted || self.voice.is_some()) {
+            return Ok(());
+        }
+
+        let msg = CsvcMsgVoiceData::decode(bytes)
+            .map_err(|e| ParseError::decode("Failed to decode bytes into CsvcMsgVoiceData", e))?;
+        let tickrate = *self.state.tickrate.lock() as f64;
+        let time = if tickrate > 0.0 {
+            self.ingame_tick as f64 / tickrate
+        } else {
+            0.0
+        };
+        let packet = VoicePacket::new(self.ingame_tick, time, msg);
+
+        // A packet that can't be read only costs its own audio.
+        if let Some(voice) = &mut self.voice {
+            if voice.add(&packet).is_err() {
+                voice.skipped_packets += 1;
+            }
+        }
+
+        if wanted {
+            self.dispatch(&packet);
+            if self.dispatcher.has_listeners(VOICE_DATA_CHANNEL) {
+                self.emit(VOICE_DATA_CHANNEL, packet);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle_sounds(&mut self, bytes: &[u8]) -> Result<(), ParseError> {
+        if self.fast_forwarding || !self.wants::<Sound>(SOUND_CHANNEL) {
+            return Ok(());
+        }
+
+        let msg = CsvcMsgSounds::decode(bytes)
+            .map_err(|e| ParseError::decode("Failed to decode bytes into CsvcMsgSounds", e))?;
+
+        let sounds = {
+            let string_table_entries = self.state.string_table_entries.lock();
+            let sound_precache = string_table_entries.get(ST_NAME_SOUND_PRECACHE);
+            read_sounds(&msg, self.ingame_tick, |sound_num| {
+                let (name, _) = sound_precache?.get(&(sound_num as i32))?;
+                Some(name.to_owned())
+            })
+        };
+
+        for sound in sounds {
+            self.dispatch(&sound);
+            if self.dispatcher.has_listeners(SOUND_CHANNEL) {
+                self.emit(SOUND_CHANNEL, sound);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle_set_convars(&mut self, bytes: &[u8]) -> Result<(), ParseError> {
+        let state = &self.state;
+        let msg = CnetMsgSetConVar::decode(bytes)
+            .map_err(|e| ParseError::decode("Failed to decode bytes into CnetMsgSetConVar", e))?;
 
         if let Some(convars) = msg.convars {
             for cvar in convars.cvars {
-                SERVER_CONVARS.lock().await.insert(cvar.name, cvar.value);
+                state.server_convars.lock().insert(cvar.name, cvar.value);
             }
         }
-    }
 
-    async fn handle_game_event(&mut self, bytes: &[u8]) {
-        let msg =
-            CsvcMsgGameEvent::decode(bytes).expect("Failed to decode bytes into CsvcMsgGameEvent.");
+        Ok(())
+    }
 
-        let game_event = &self.game_event_list[&msg.eventid];
+    fn handle_game_event(&mut self, msg: CsvcMsgGameEvent) -> Result<(), ParseError> {
+        let game_event = match self.game_event_list.get(&msg.eventid) {
+            Some(game_event) => game_event.to_owned(),
+            None => {
+                return Err(ParseError::malformed(format!(
+                    "Unknown game event id {}",
+                    msg.eventid
+                )))
+            }
+        };
 
         match game_event.name.as_str() {
-            "bomb_planted" => {
-                self.dispatcher
-                    .emit(&game_event.name, game_event.into_type::<BombPlanted>(msg))
-                    .await;
-            }
-            "flashbang_detonate" => {
-                self.dispatcher
-                    .emit(
-                        &game_event.name,
-                        game_event.into_type::<FlashbangDetonate>(msg),
-                    )
-                    .await;
+            "round_announce_match_start" => self.current_round = 0,
+            "round_start" => {
+                self.current_round += 1;
+                self.round_started = true;
+            }
+            _ => {}
+        }
+
+        if self.fast_forwarding {
+            return Ok(());
+        }
+
+        let untyped_listened = self.dispatcher.has_listeners(GAME_EVENT_CHANNEL);
+        if untyped_listened || self.handlers.has::<UntypedGameEvent>() {
+            let untyped = UntypedGameEvent {
+                name: game_event.name.to_owned(),
+                tick: self.ingame_tick,
+                keys: game_event.to_values(&msg),
+            };
+            self.dispatch(&untyped);
+            if untyped_listened {
+                self.emit(GAME_EVENT_CHANNEL, untyped);
             }
+        }
+
+        if let Err(e) = self.emit_typed_game_event(&game_event, &msg) {
+            let offset = self.bitreader.actual_position() >> 3;
+            self.event_errors.push(e.at(self.ingame_tick, offset));
+        }
+
+        Ok(())
+    }
+
+    fn emit_typed_game_event(
+        &mut self,
+        game_event: &GameEvent,
+        msg: &CsvcMsgGameEvent,
+    ) -> Result<(), ParseError> {
+        match game_event.name.as_str() {
+            "bomb_planted" => self.emit_game_event::<BombPlanted>(game_event, msg)?,
+            "flashbang_detonate" => self.emit_game_event::<FlashbangDetonate>(game_event, msg)?,
             "smokegrenade_detonate" => {
-                self.dispatcher
-                    .emit(
-                        &game_event.name,
-                        game_event.into_type::<SmokeGrenadeDetonate>(msg),
-                    )
-                    .await;
+                self.emit_game_event::<SmokeGrenadeDetonate>(game_event, msg)?
             }
             "smokegrenade_expired" => {
-                self.dispatcher
-                    .emit(
-                        &game_event.name,
-                        game_event.into_type::<SmokeGrenadeExpired>(msg),
-                    )
-                    .await;
-            }
-            "player_footstep" => {
-                self.dispatcher
-                    .emit(
-                        &game_event.name,
-                        game_event.into_type::<PlayerFootstep>(msg),
-                    )
-                    .await;
+                self.emit_game_event::<SmokeGrenadeExpired>(game_event, msg)?
             }
+            "player_footstep" => self.emit_game_event::<PlayerFootstep>(game_event, msg)?,
             "player_spawn" => {
-                let obj = game_event.to_object(&msg);
-                let player_spawn = serde_json::from_value::<PlayerSpawn>(json!(obj)).unwrap();
-
-                self.dispatcher
-                    .emit(
-                        "player_spawned",
-                        (player_spawn.teamnum, player_spawn.userid),
-                    )
-                    .await;
+                let player_spawn = PlayerSpawn::try_from((game_event, msg))?;
+                self.emit(
+                    "player_spawned",
+                    (player_spawn.teamnum, player_spawn.userid),
+                );
             }
             "round_announce_match_start" => {
-                println!("MATCH STARTING");
-                self.dispatcher
-                    .emit(&game_event.name, self.ingame_tick)
-                    .await;
-            }
-            "round_start" => {
-                self.dispatcher
-                    .emit(&game_event.name, game_event.into_type::<RoundStart>(msg))
-                    .await;
+                self.emit(&game_event.name, self.ingame_tick);
             }
+            "round_start" => self.emit_game_event::<RoundStart>(game_event, msg)?,
             "round_freeze_end" => {
-                self.dispatcher
-                    .emit(&game_event.name, self.ingame_tick)
-                    .await;
+                self.emit(&game_event.name, self.ingame_tick);
             }
             "buytime_ended" => {
-                self.dispatcher
-                    .emit(&game_event.name, self.ingame_tick)
-                    .await;
-            }
-            "round_end" => {
-                self.dispatcher
-                    .emit(&game_event.name, game_event.into_type::<RoundEnd>(msg))
-                    .await;
+                self.emit(&game_event.name, self.ingame_tick);
             }
+            "round_end" => self.emit_game_event::<RoundEnd>(game_event, msg)?,
             "round_officially_ended" => {
-                self.dispatcher
-                    .emit(&game_event.name, self.ingame_tick)
-                    .await;
+                self.emit(&game_event.name, self.ingame_tick);
             }
             "player_death" => {
-                self.dispatcher
-                    .emit(&game_event.name, game_event.into_type::<PlayerDeath>(msg))
-                    .await;
+                let enriched = self.wants::<PlayerDeathEnriched>("player_death_enriched");
+                if enriched || self.wants::<PlayerDeath>(&game_event.name) {
+                    let event = PlayerDeath::try_from((game_event, msg))?;
+                    self.emit_typed(&game_event.name, &event);
+
+                    if enriched {
+                        let enriched = PlayerDeathEnriched {
+                            victim: resolve_event_player(&self.state, event.userid),
+                            attacker: resolve_event_player(&self.state, event.attacker),
+                            assister: resolve_event_player(&self.state, event.assister),
+                            event: Some(event),
+                        };
+                        self.emit_typed("player_death_enriched", &enriched);
+                    }
+                }
             }
             "player_hurt" => {
-                self.dispatcher
-                    .emit(&game_event.name, game_event.into_type::<PlayerHurt>(msg))
-                    .await;
+                let enriched = self.wants::<PlayerHurtEnriched>("player_hurt_enriched");
+                if enriched || self.wants::<PlayerHurt>(&game_event.name) {
+                    let event = PlayerHurt::try_from((game_event, msg))?;
+                    self.emit_typed(&game_event.name, &event);
+
+                    if enriched {
+                        let enriched = PlayerHurtEnriched {
+                            victim: resolve_event_player(&self.state, event.userid),
+                            attacker: resolve_event_player(&self.state, event.attacker),
+                            event: Some(event),
+                        };
+                        self.emit_typed("player_hurt_enriched", &enriched);
+                    }
+                }
             }
             "weapon_fire" => {
-                self.dispatcher
-                    .emit(&game_event.name, game_event.into_type::<WeaponFire>(msg))
-                    .await;
+                let enriched = self.wants::<WeaponFireEnriched>("weapon_fire_enriched");
+                if enriched || self.wants::<WeaponFire>(&game_event.name) {
+                    let event = WeaponFire::try_from((game_event, msg))?;
+                    self.emit_typed(&game_event.name, &event);
+
+                    if enriched {
+                        let enriched = WeaponFireEnriched {
+                            player: resolve_event_player(&self.state, event.userid),
+                            event: Some(event),
+                        };
+                        self.emit_typed("weapon_fire_enriched", &enriched);
+                    }
+                }
+            }
+            "bomb_beginplant" => self.emit_game_event::<BombBeginPlant>(game_event, msg)?,
+            "bomb_abortplant" => self.emit_game_event::<BombAbortPlant>(game_event, msg)?,
+            "bomb_defused" => self.emit_game_event::<BombDefused>(game_event, msg)?,
+            "bomb_exploded" => self.emit_game_event::<BombExploded>(game_event, msg)?,
+            "bomb_dropped" => self.emit_game_event::<BombDropped>(game_event, msg)?,
+            "bomb_pickup" => self.emit_game_event::<BombPickup>(game_event, msg)?,
+            "bomb_begindefuse" => self.emit_game_event::<BombBeginDefuse>(game_event, msg)?,
+            "bomb_abortdefuse" => self.emit_game_event::<BombAbortDefuse>(game_event, msg)?,
+            "item_purchase" => self.emit_game_event::<ItemPurchase>(game_event, msg)?,
+            "item_pickup" => self.emit_game_event::<ItemPickup>(game_event, msg)?,
+            "item_remove" => self.emit_game_event::<ItemRemove>(game_event, msg)?,
+            "item_equip" => self.emit_game_event::<ItemEquip>(game_event, msg)?,
+            "ammo_pickup" => self.emit_game_event::<AmmoPickup>(game_event, msg)?,
+            "player_blind" => self.emit_game_event::<PlayerBlind>(game_event, msg)?,
+            "player_jump" => self.emit_game_event::<PlayerJump>(game_event, msg)?,
+            "player_falldamage" => self.emit_game_event::<PlayerFallDamage>(game_event, msg)?,
+            "hegrenade_detonate" => self.emit_game_event::<HeGrenadeDetonate>(game_event, msg)?,
+            "molotov_detonate" => self.emit_game_event::<MolotovDetonate>(game_event, msg)?,
+            "decoy_started" => self.emit_game_event::<DecoyStarted>(game_event, msg)?,
+            "decoy_detonate" => self.emit_game_event::<DecoyDetonate>(game_event, msg)?,
+            "decoy_firing" => self.emit_game_event::<DecoyFiring>(game_event, msg)?,
+            "tagrenade_detonate" => self.emit_game_event::<TaGrenadeDetonate>(game_event, msg)?,
+            "inferno_startburn" => self.emit_game_event::<InfernoStartBurn>(game_event, msg)?,
+            "inferno_expire" => self.emit_game_event::<InfernoExpire>(game_event, msg)?,
+            "inferno_extinguish" => self.emit_game_event::<InfernoExtinguish>(game_event, msg)?,
+            "grenade_thrown" => self.emit_game_event::<GrenadeThrown>(game_event, msg)?,
+            "grenade_bounce" => self.emit_game_event::<GrenadeBounce>(game_event, msg)?,
+            "bullet_impact" => self.emit_game_event::<BulletImpact>(game_event, msg)?,
+            "player_connect" => self.emit_game_event::<PlayerConnect>(game_event, msg)?,
+            "player_connect_full" => self.emit_game_event::<PlayerConnectFull>(game_event, msg)?,
+            "player_disconnect" => self.emit_game_event::<PlayerDisconnect>(game_event, msg)?,
+            "player_team" => self.emit_game_event::<PlayerTeam>(game_event, msg)?,
+            "player_changename" => self.emit_game_event::<PlayerChangeName>(game_event, msg)?,
+            "player_given_c4" => self.emit_game_event::<PlayerGivenC4>(game_event, msg)?,
+            "cs_win_panel_round" => self.emit_game_event::<CsWinPanelRound>(game_event, msg)?,
+            "cs_win_panel_match" => self.emit_game_event::<CsWinPanelMatch>(game_event, msg)?,
+            "round_mvp" => self.emit_game_event::<RoundMvp>(game_event, msg)?,
+            "match_end_conditions" => {
+                self.emit_game_event::<MatchEndConditions>(game_event, msg)?
+            }
+            "weapon_reload" => self.emit_game_event::<WeaponReload>(game_event, msg)?,
+            "weapon_zoom" => self.emit_game_event::<WeaponZoom>(game_event, msg)?,
+            "weapon_fire_on_empty" => self.emit_game_event::<WeaponFireOnEmpty>(game_event, msg)?,
+            "weapon_outofammo" => self.emit_game_event::<WeaponOutOfAmmo>(game_event, msg)?,
+            "silencer_detach" => self.emit_game_event::<SilencerDetach>(game_event, msg)?,
+            "inspect_weapon" => self.emit_game_event::<InspectWeapon>(game_event, msg)?,
+            "bot_takeover" => self.emit_game_event::<BotTakeover>(game_event, msg)?,
+            "enter_buyzone" => self.emit_game_event::<EnterBuyzone>(game_event, msg)?,
+            "exit_buyzone" => self.emit_game_event::<ExitBuyzone>(game_event, msg)?,
+            "enter_bombzone" => self.emit_game_event::<EnterBombzone>(game_event, msg)?,
+            "exit_bombzone" => self.emit_game_event::<ExitBombzone>(game_event, msg)?,
+            "door_moving" => self.emit_game_event::<DoorMoving>(game_event, msg)?,
+            "other_death" => self.emit_game_event::<OtherDeath>(game_event, msg)?,
+            "round_prestart"
+            | "round_poststart"
+            | "begin_new_match"
+            | "cs_match_end_restart"
+            | "cs_pre_restart"
+            | "round_time_warning"
+            | "announce_phase_end"
+            | "cs_intermission"
+            | "cs_round_start_beep"
+            | "cs_round_final_beep" => {
+                self.emit(&game_event.name, self.ingame_tick);
             }
             _ => {}
         };
+
+        Ok(())
     }
 
-    async fn handle_game_event_list(&mut self, bytes: &[u8]) {
-        let msg = CsvcMsgGameEventList::decode(bytes)
-            .expect("Failed to decode bytes into CsvcMsgGameEventList.");
+    fn wants<T: 'static>(&self, channel: &str) -> bool {
+        self.handlers.has::<T>() || self.dispatcher.has_listeners(channel)
+    }
+
+    fn emit<T: Serialize>(&mut self, event: &str, value: T) {
+        self.dispatcher.emit(event, value);
+    }
+
+    fn dispatch<T: 'static>(&mut self, event: &T) {
+        let ctx = EventContext {
+            tick: self.ingame_tick,
+            round: self.current_round,
+            state: &self.state,
+        };
+        self.handlers.dispatch(event, &ctx);
+    }
+
+    // Typed handlers get the event as is; dispatcher listeners on `channel` get it prost
+    // encoded, as before.
+    fn emit_typed<T: Message + 'static>(&mut self, channel: &str, event: &T) {
+        self.dispatch(event);
+        if self.dispatcher.has_listeners(channel) {
+            self.emit(channel, event.encode_to_vec());
+        }
+    }
+
+    // Only builds the event when somebody is listening for it.
+    fn emit_game_event<T>(
+        &mut self,
+        game_event: &GameEvent,
+        msg: &CsvcMsgGameEvent,
+    ) -> Result<(), ParseError>
+    where
+        T: for<'a> TryFrom<(&'a GameEvent, &'a CsvcMsgGameEvent), Error = ParseError>
+            + Message
+            + 'static,
+    {
+        if self.wants::<T>(&game_event.name) {
+            let event = T::try_from((game_event, msg))?;
+            self.emit_typed(&game_event.name, &event);
+        }
+        Ok(())
+    }
+
+    fn handle_game_event_list(&mut self, bytes: &[u8]) -> Result<(), ParseError> {
+        let msg = CsvcMsgGameEventList::decode(bytes).map_err(|e| {
+            ParseError::decode("Failed to decode bytes into CsvcMsgGameEventList", e)
+        })?;
 
         for d in msg.descriptors {
             self.game_event_list.insert(d.eventid, GameEvent::from(d));
         }
+
+        Ok(())
     }
 
     #[inline]
-    async fn handle_packet_entities(&mut self, bytes: &[u8]) {
-        let pe = CsvcMsgPacketEntities::decode(bytes)
-            .expect("Failed to decode bytes into CsvcMsgPacketEntities");
+    fn handle_temp_entities(&mut self, bytes: &[u8]) -> Result<(), ParseError> {
+        if self.fast_forwarding || !self.wants::<TempEntity>(TEMP_ENTITY_CHANNEL) {
+            return Ok(());
+        }
 
-        let mut server_classes = SERVER_CLASSES.lock().await;
-        let mut prop_indices_vec = PROP_INDICES_VEC.lock().await;
-        let server_class_bits = SERVER_CLASS_BITS.lock().await;
+        let state = Arc::clone(&self.state);
+        for temp_entity in read_temp_entities(&state, bytes, self.ingame_tick)? {
+            self.dispatch(&temp_entity);
+            if self.dispatcher.has_listeners(TEMP_ENTITY_CHANNEL) {
+                self.emit(TEMP_ENTITY_CHANNEL, temp_entity);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle_packet_entities(&mut self, bytes: &[u8]) -> Result<(), ParseError> {
+        let state = Arc::clone(&self.state);
+        let pe = CsvcMsgPacketEntities::decode(bytes).map_err(|e| {
+            ParseError::decode("Failed to decode bytes into CsvcMsgPacketEntities", e)
+        })?;
+
+        let mut server_classes = state.server_classes.lock();
+        let mut prop_indices_vec = state.prop_indices_vec.lock();
+        let server_class_bits = state.server_class_bits.lock();
 
         let mut r = BitReader::new_small_bit_reader(Cursor::new(pe.entity_data()));
 
@@ -582,39 +1485,23 @@ impl Parser {
             if cmd & 1 == 0 {
                 if cmd & 2 != 0 {
                     let entity = read_enter_pvs(
+                        &state,
                         &mut r,
                         current_entity,
                         &mut server_classes,
                         &mut prop_indices_vec,
                         *server_class_bits,
-                    )
-                    .await;
+                    )?;
 
-                    {
-                        if let Some(raw_player) = RAW_PLAYERS.lock().await.get(&(entity.id - 1)) {
-                            if let (Some(player_info_by_user_id), Some(player_info_by_steam_id)) = (
-                                PLAYER_INFO_BY_USER_ID
-                                    .lock()
-                                    .await
-                                    .get_mut(&(raw_player.user_id as i32)),
-                                PLAYER_INFO_BY_STEAM_ID
-                                    .lock()
-                                    .await
-                                    .get_mut(&raw_player.xuid),
-                            ) {
-                                player_info_by_user_id.entity_id = entity.id;
-                                player_info_by_steam_id.entity_id = entity.id;
-                            }
-                        }
-                    }
+                    link_player_info(&state, entity.id);
 
-                    ENTITIES
+                    state
+                        .entities
                         .lock()
-                        .await
                         .insert(current_entity, entity.to_owned());
 
-                    if let Some(proj) = GRENADE_PROJECTILES.lock().await.get_mut(&entity.id) {
-                        _retrieve_potential_thrower_owner(&entity, proj).await;
+                    if let Some(proj) = state.grenade_projectiles.lock().get_mut(&entity.id) {
+                        _retrieve_potential_thrower_owner(&state, &entity, proj);
 
                         proj.weapon_instance = get_player_weapon(&proj.thrower, proj.wep_type);
 
@@ -624,104 +1511,210 @@ impl Parser {
                             &proj.owner
                         };
 
-                        _add_thrown_grenade(person, &proj.weapon_instance).await;
+                        _add_thrown_grenade(&state, person, &proj.weapon_instance);
 
-                        self.dispatcher
-                            .emit("grenade_projectile_throw", entity.id)
-                            .await;
+                        self.emit("grenade_projectile_throw", entity.id);
                     }
-                } else if let Some(entity) = ENTITIES.lock().await.get_mut(&current_entity) {
-                    entity.apply_update(&mut r, &mut prop_indices_vec).await;
+                } else if let Some(entity) = state.entities.lock().get_mut(&current_entity) {
+                    entity.apply_update(&state, &mut r, &mut prop_indices_vec)?;
                 }
             } else if cmd & 2 != 0 {
-                if let Some(entity) = &mut ENTITIES.lock().await.remove(&current_entity) {
-                    if let Some(proj) = &mut GRENADE_PROJECTILES.lock().await.remove(&entity.id) {
+                if let Some(entity) = &mut state.entities.lock().remove(&current_entity) {
+                    state.weapons.lock().remove(&entity.id);
+                    if let Some(proj) = &mut state.grenade_projectiles.lock().remove(&entity.id) {
                         if let Some(thrower) = &proj.thrower {
-                            self.dispatcher
-                                .emit(
-                                    "grenade_projectile_destroyed",
-                                    (
-                                        entity.id,
-                                        proj.wep_type,
-                                        thrower.entity_id,
-                                        proj.trajectory.clone(),
-                                    ),
-                                )
-                                .await;
+                            self.emit(
+                                "grenade_projectile_destroyed",
+                                (
+                                    entity.id,
+                                    proj.wep_type,
+                                    thrower.entity_id,
+                                    proj.trajectory.clone(),
+                                ),
+                            );
                         }
                     }
                 }
             }
         }
+
+        Ok(())
     }
 }
 
+// Reads and validates only the fixed-size header at the start of a demo, without setting up
+// a parser.
+pub fn read_header<P: AsRef<Path>>(path: P) -> Result<Header, ParseError> {
+    let mut buf = vec![0; HEADER_LENGTH];
+    DemoSource::open(path)?
+        .read_exact(&mut buf)
+        .map_err(|e| match e.kind() {
+            ErrorKind::UnexpectedEof => {
+                ParseError::InvalidHeader("file is shorter than a demo header".to_string())
+            }
+            _ => ParseError::Io(e),
+        })?;
+
+    let header = Header::_parse(&mut BitReader::new_bit_reader(
+        Cursor::new(buf),
+        vec![0; HEADER_LENGTH],
+    ));
+    header.validate()?;
+
+    Ok(header)
+}
+
 #[inline]
-async fn bind_entities() {
-    bind_team_states().await;
-    bind_players().await;
-    bind_weapons().await;
+fn bind_entities(state: &ParserState) {
+    bind_team_states(state);
+    bind_players(state);
+    bind_weapons(state);
 }
 
 #[inline]
-async fn handle_create_string_table(bytes: &[u8]) {
-    let msg = CsvcMsgCreateStringTable::decode(bytes)
-        .expect("Failed to decode bytes into CsvcMsgCreateStringTable.");
+fn handle_create_string_table(state: &ParserState, bytes: &[u8]) -> Result<(), ParseError> {
+    let msg = CsvcMsgCreateStringTable::decode(bytes).map_err(|e| {
+        ParseError::decode("Failed to decode bytes into CsvcMsgCreateStringTable", e)
+    })?;
 
-    process_string_table(&msg).await;
+    process_string_table(state, &msg)?;
 
-    STRING_TABLES.lock().await.push(msg);
+    state.string_tables.lock().push(msg);
+
+    Ok(())
 }
 
 #[inline]
-async fn handle_update_string_table(bytes: &[u8]) {
-    let msg = CsvcMsgUpdateStringTable::decode(bytes)
-        .expect("Failed to decode bytes into CsvcMsgUpdateStringTable.");
-    let mut string_tables = STRING_TABLES.lock().await;
-    let create_msg = unsafe { string_tables.get_unchecked_mut(msg.table_id() as usize) };
+fn handle_update_string_table(state: &ParserState, bytes: &[u8]) -> Result<(), ParseError> {
+    let msg = CsvcMsgUpdateStringTable::decode(bytes).map_err(|e| {
+        ParseError::decode("Failed to decode bytes into CsvcMsgUpdateStringTable", e)
+    })?;
+    let mut string_tables = state.string_tables.lock();
+    let create_msg = match string_tables.get_mut(msg.table_id() as usize) {
+        Some(create_msg) => create_msg,
+        None => {
+            return Err(ParseError::malformed(format!(
+                "Unknown string table id {}",
+                msg.table_id()
+            )))
+        }
+    };
     match create_msg.name() {
-        ST_NAME_USER_INFO | ST_NAME_INSTANCE_BASELINE | ST_NAME_MODEL_PRECACHE => {
+        ST_NAME_USER_INFO
+        | ST_NAME_INSTANCE_BASELINE
+        | ST_NAME_MODEL_PRECACHE
+        | ST_NAME_SOUND_PRECACHE => {
             create_msg.num_entries = msg.num_changed_entries;
             create_msg.string_data = msg.string_data;
 
-            process_string_table(create_msg).await;
+            process_string_table(state, create_msg)?;
         }
         _ => {}
     }
+
+    Ok(())
+}
+
+// dem_stringtables frames hold every table in full, each entry as a string with optional user
+// data, followed by the client side entries.
+fn handle_string_tables_dump(state: &ParserState, bytes: &[u8]) -> Result<(), ParseError> {
+    let mut model_precache = state.model_precache.lock();
+    let mut server_classes = state.server_classes.lock();
+
+    let mut br = BitReader::new_small_bit_reader(Cursor::new(bytes));
+
+    let num_tables = br.read_single_byte();
+    for _ in 0..num_tables {
+        let table = br.read_string();
+
+        let num_entries = br.read_int(TWO_BYTES);
+        for entry_index in 0..num_entries {
+            let entry = br.read_string();
+            let user_data = if br.read_bit() {
+                let size = br.read_int(TWO_BYTES);
+                br.read_bytes(size)
+            } else {
+                Vec::new()
+            };
+            if user_data.is_empty() && table != ST_NAME_SOUND_PRECACHE {
+                continue;
+            }
+
+            if table == ST_NAME_MODEL_PRECACHE && model_precache.len() <= entry_index {
+                model_precache.resize(entry_index + 1, String::new());
+            }
+            apply_string_table_entry(
+                state,
+                &table,
+                entry_index as i32,
+                &entry,
+                &user_data,
+                &mut model_precache,
+                &mut server_classes,
+            )?;
+        }
+
+        if br.read_bit() {
+            let num_client_entries = br.read_int(TWO_BYTES);
+            for _ in 0..num_client_entries {
+                br.read_string();
+                if br.read_bit() {
+                    let size = br.read_int(TWO_BYTES);
+                    br.skip(size << BEGIN_CHUNK_SHIFT);
+                }
+            }
+        }
+
+        if table == ST_NAME_MODEL_PRECACHE {
+            index_grenade_models(state, &model_precache);
+        }
+    }
+
+    Ok(())
 }
 
 #[inline]
-async fn read_enter_pvs<T: Read + Seek + Send>(
+fn read_enter_pvs<T: Read + Seek + Send>(
+    state: &Arc<ParserState>,
     r: &mut BitReader<T>,
     id: i32,
     server_classes: &mut [ServerClass],
     prop_indices_vec: &mut Vec<u32>,
     server_class_bits: i32,
-) -> Entity {
+) -> Result<Entity, ParseError> {
     let sc_id = r.read_int(server_class_bits as usize);
     r.skip(10);
-    server_classes[sc_id]
-        .new_entity(r, id, prop_indices_vec)
-        .await
+    match server_classes.get_mut(sc_id) {
+        Some(server_class) => server_class.new_entity(state, r, id, prop_indices_vec),
+        None => Err(ParseError::malformed(format!(
+            "Unknown server class {sc_id} for entity {id}"
+        ))),
+    }
 }
 
 #[inline]
-async fn process_string_table(tab: &CsvcMsgCreateStringTable) {
-    let mut user_data = USER_DATA_VEC.lock().await;
-    let mut entry = ENTRY_STRING.lock().await;
-    let mut hist = HIST_VEC.lock().await;
-    let mut model_precache = MODEL_PRECACHE.lock().await;
-    let mut server_classes = SERVER_CLASSES.lock().await;
+fn process_string_table(
+    state: &ParserState,
+    tab: &CsvcMsgCreateStringTable,
+) -> Result<(), ParseError> {
+    let mut user_data = state.user_data_vec.lock();
+    let mut entry = state.entry_string.lock();
+    let mut hist = state.hist_vec.lock();
+    let mut model_precache = state.model_precache.lock();
+    let mut server_classes = state.server_classes.lock();
 
     if tab.name() == ST_NAME_MODEL_PRECACHE {
-        let size = tab.max_entries() as usize - model_precache.len();
+        let size = (tab.max_entries().max(0) as usize).saturating_sub(model_precache.len());
         model_precache.append(&mut vec!["".to_string(); size]);
     }
 
     let mut br = BitReader::new_small_bit_reader(Cursor::new(tab.string_data()));
 
     if br.read_bit() {
-        panic!("Can't decode");
+        return Err(ParseError::malformed(format!(
+            "Can't decode string table {}",
+            tab.name()
+        )));
     }
 
     let mut n_tmp = tab.max_entries();
@@ -748,7 +1741,11 @@ async fn process_string_table(tab: &CsvcMsgCreateStringTable) {
         last_entry = entry_index;
 
         if entry_index < 0 || entry_index >= tab.max_entries() {
-            panic!("Something went to shit");
+            return Err(ParseError::malformed(format!(
+                "String table {} entry index {entry_index} out of range (max {})",
+                tab.name(),
+                tab.max_entries()
+            )));
         }
 
         entry.clear();
@@ -757,7 +1754,15 @@ async fn process_string_table(tab: &CsvcMsgCreateStringTable) {
                 let idx = br.read_int(MAX_VARINT32_BYTES);
                 let bytes_2_cp = br.read_int(MAX_VARINT32_BYTES);
 
-                entry.extend(hist[idx][..bytes_2_cp].chars());
+                match hist.get(idx).and_then(|h| h.get(..bytes_2_cp)) {
+                    Some(prefix) => entry.extend(prefix.chars()),
+                    None => {
+                        return Err(ParseError::malformed(format!(
+                            "String table {} history reference {idx}[..{bytes_2_cp}] out of range",
+                            tab.name()
+                        )))
+                    }
+                }
                 entry.extend(br.read_string().chars());
             } else {
                 entry.extend(br.read_string().chars());
@@ -784,62 +1789,127 @@ async fn process_string_table(tab: &CsvcMsgCreateStringTable) {
             }
         }
 
-        if user_data.is_empty() {
+        // Sound names are all there is to the sound precache entries.
+        if user_data.is_empty() && tab.name() != ST_NAME_SOUND_PRECACHE {
             continue;
         }
 
-        match tab.name() {
-            ST_NAME_USER_INFO => {
-                let player_info: PlayerInfo = parse_player_info(&user_data);
+        apply_string_table_entry(
+            state,
+            tab.name(),
+            entry_index,
+            &entry,
+            &user_data,
+            &mut model_precache,
+            &mut server_classes,
+        )?;
+    }
 
-                PLAYER_INFO_BY_USER_ID
-                    .lock()
-                    .await
-                    .insert(player_info.user_id as i32, player_info.to_owned());
-                RAW_PLAYERS
-                    .lock()
-                    .await
-                    .insert(entry_index, player_info.to_owned());
-                PLAYER_INFO_BY_STEAM_ID
+    if tab.name() == ST_NAME_MODEL_PRECACHE {
+        index_grenade_models(state, &model_precache);
+    }
+
+    Ok(())
+}
+
+fn apply_string_table_entry(
+    state: &ParserState,
+    table: &str,
+    entry_index: i32,
+    entry: &str,
+    user_data: &[u8],
+    model_precache: &mut [String],
+    server_classes: &mut [ServerClass],
+) -> Result<(), ParseError> {
+    if let ST_NAME_USER_INFO
+    | ST_NAME_INSTANCE_BASELINE
+    | ST_NAME_MODEL_PRECACHE
+    | ST_NAME_SOUND_PRECACHE = table
+    {
+        state
+            .string_table_entries
+            .lock()
+            .entry(table.to_string())
+            .or_default()
+            .insert(entry_index, (entry.to_owned(), user_data.to_owned()));
+    }
+
+    match table {
+        ST_NAME_USER_INFO => {
+            let player_info: PlayerInfo = parse_player_info(user_data);
+
+            state
+                .player_info_by_user_id
+                .lock()
+                .insert(player_info.user_id as i32, player_info.to_owned());
+            state
+                .raw_players
+                .lock()
+                .insert(entry_index, player_info.to_owned());
+            state
+                .player_info_by_steam_id
+                .lock()
+                .insert(player_info.xuid, player_info);
+        }
+        ST_NAME_INSTANCE_BASELINE => {
+            let class_id = entry
+                .parse::<usize>()
+                .map_err(|_| ParseError::malformed(format!("Error parsing class_id {entry}")))?;
+            if let Some(sc) = server_classes.get_mut(class_id) {
+                sc.instance_baseline = Some(user_data.to_owned());
+            } else {
+                state
+                    .instance_baselines
                     .lock()
-                    .await
-                    .insert(player_info.xuid, player_info);
-            }
-            ST_NAME_INSTANCE_BASELINE => {
-                let class_id = entry.parse::<usize>().expect("Error parsing class_id.");
-                if let Some(sc) = server_classes.get_mut(class_id) {
-                    sc.instance_baseline = Some(user_data.to_owned());
-                } else {
-                    INSTANCE_BASELINES
-                        .lock()
-                        .await
-                        .insert(class_id as i32, user_data.to_owned());
-                }
+                    .insert(class_id as i32, user_data.to_owned());
             }
-            ST_NAME_MODEL_PRECACHE => {
-                model_precache[entry_index as usize] = entry.to_owned();
+        }
+        ST_NAME_MODEL_PRECACHE => {
+            if let Some(model) = model_precache.get_mut(entry_index as usize) {
+                *model = entry.to_owned();
             }
-            _ => {}
         }
+        _ => {}
     }
 
-    if tab.name() == ST_NAME_MODEL_PRECACHE {
-        let hm = HashMap::from([
-            ("flashbang", EQ_FLASH),
-            ("fraggrenade", EQ_HE),
-            ("smokegrenade", EQ_SMOKE),
-            ("molotov", EQ_MOLOTOV),
-            ("incendiarygrenade", EQ_INCENDIARY),
-            ("decoy", EQ_DECOY),
-        ]);
-        for (i, name) in model_precache.iter().enumerate() {
-            for (eq_name, eq) in hm.iter() {
-                if name.contains(eq_name) {
-                    GRENADE_MODEL_INDICES
-                        .lock()
-                        .await
-                        .insert(i as i32, eq.to_owned());
-                }
+    Ok(())
+}
+
+// Points the player info of the player in entity slot `entity_id` at the entity.
+fn link_player_info(state: &ParserState, entity_id: i32) {
+    if let Some(raw_player) = state.raw_players.lock().get(&(entity_id - 1)) {
+        if let (Some(player_info_by_user_id), Some(player_info_by_steam_id)) = (
+            state
+                .player_info_by_user_id
+                .lock()
+                .get_mut(&(raw_player.user_id as i32)),
+            state
+                .player_info_by_steam_id
+                .lock()
+                .get_mut(&raw_player.xuid),
+        ) {
+            player_info_by_user_id.entity_id = entity_id;
+            player_info_by_steam_id.entity_id = entity_id;
+        }
+    }
+}
+
+fn index_grenade_models(state: &ParserState, model_precache: &[String]) {
+    let hm = HashMap::from([
+        ("flashbang", EQ_FLASH),
+        ("fraggrenade", EQ_HE),
+        ("smokegrenade", EQ_SMOKE),
+        ("molotov", EQ_MOLOTOV),
+        ("incendiarygrenade", EQ_INCENDIARY),
+        ("decoy", EQ_DECOY),
+    ]);
+    for (i, name) in model_precache.iter().enumerate() {
+        for (eq_name, eq) in hm.iter() {
+            if name.contains(eq_name) {
+                state
+                    .grenade_model_indices
+                    .lock()
+                    .insert(i as i32, eq.to_owned());
             }
         }
     }
@@ -851,34 +1921,32 @@ fn parse_player_info(bytes: &[u8]) -> PlayerInfo {
 }
 
 #[inline]
-#[async_recursion::async_recursion]
-async fn gather_prerequisites(
+fn gather_prerequisites(
+    state: &ParserState,
     send_table: &SendTable,
     collect_base_classes: bool,
     sc_map: &mut HashMap<String, ServerClass>,
     ex_map: &mut HashMap<String, ExcludeEntry>,
-) {
+) -> Result<(), ParseError> {
     for stp in send_table.properties.iter() {
         if stp.flags.has_flag_set(PROP_FLAG_EXCLUDE) {
             let exclude = ExcludeEntry {
-                var_name: stp.name.to_owned(),
                 dt_name: stp.dt_name.to_owned(),
-                excluding_dt: send_table.name.to_owned(),
             };
             ex_map.insert(stp.name.to_owned(), exclude);
         }
 
         if stp.raw_type == 6 {
-            let st = SEND_TABLES_BY_NAME.lock().await[&stp.dt_name].to_owned();
+            let st = send_table_by_name(state, &stp.dt_name)?;
             gather_prerequisites(
+                state,
                 &st,
                 collect_base_classes && stp.name == "baseclass",
                 sc_map,
                 ex_map,
-            )
-            .await;
+            )?;
             if let (Some(scbn), true, "baseclass") = (
-                SERVER_CLASSES_BY_NAME.lock().await.get(&stp.dt_name),
+                state.server_classes_by_name.lock().get(&stp.dt_name),
                 collect_base_classes,
                 stp.name.as_str(),
             ) {
@@ -886,73 +1954,101 @@ async fn gather_prerequisites(
             }
         }
     }
+    Ok(())
+}
+
+#[inline]
+fn send_table_by_name(state: &ParserState, name: &str) -> Result<SendTable, ParseError> {
+    state
+        .send_tables_by_name
+        .lock()
+        .get(name)
+        .cloned()
+        .ok_or_else(|| ParseError::malformed(format!("Unknown send table {name}")))
 }
 
-async fn gather_props(
+fn gather_props(
+    state: &ParserState,
     send_table: &SendTable,
     server_class_index: usize,
     prefix: &str,
     server_classes: &mut [ServerClass],
     ex_map: &HashMap<String, ExcludeEntry>,
-) {
+) -> Result<(), ParseError> {
     let mut tmp_flattened_props: Vec<FlattenedPropEntry> = Vec::with_capacity(FLATTENED_PROPS_CAP);
     gather_props_iterate(
+        state,
         send_table,
         server_class_index,
         prefix,
         &mut tmp_flattened_props,
         server_classes,
         ex_map,
-    )
-    .await;
+    )?;
     if let Some(flattened_props) = &mut server_classes[server_class_index].flattened_props {
         flattened_props.append(&mut tmp_flattened_props);
     } else {
         server_classes[server_class_index].flattened_props = Some(tmp_flattened_props);
     }
+    Ok(())
 }
 
-#[async_recursion::async_recursion]
-async fn gather_props_iterate(
+fn gather_props_iterate(
+    state: &ParserState,
     send_table: &SendTable,
     server_class_index: usize,
     prefix: &str,
     flattened_props: &mut Vec<FlattenedPropEntry>,
     server_classes: &mut [ServerClass],
     ex_map: &HashMap<String, ExcludeEntry>,
-) {
+) -> Result<(), ParseError> {
     for (i, prop) in send_table.properties.iter().enumerate() {
         if !(prop.flags.has_flag_set(PROP_FLAG_INSIDE_ARRAY)
             || prop.flags.has_flag_set(PROP_FLAG_EXCLUDE)
             || is_prop_excluded(send_table, prop, ex_map))
         {
             if prop.raw_type == PROP_TYPE_DATA_TABLE {
-                let s_table = SEND_TABLES_BY_NAME.lock().await[&prop.dt_name].to_owned();
+                let s_table = send_table_by_name(state, &prop.dt_name)?;
 
                 if prop.flags.has_flag_set(PROP_FLAG_COLLAPSIBLE) {
                     gather_props_iterate(
+                        state,
                         &s_table,
                         server_class_index,
                         prefix,
                         flattened_props,
                         server_classes,
                         ex_map,
-                    )
-                    .await;
+                    )?;
                 } else {
                     let mut n_fix = prefix.to_string();
                     if !prop.name.is_empty() {
                         n_fix.push_str(&format!("{}.", &prop.name));
                     }
-                    gather_props(&s_table, server_class_index, &n_fix, server_classes, ex_map)
-                        .await;
+                    gather_props(
+                        state,
+                        &s_table,
+                        server_class_index,
+                        &n_fix,
+                        server_classes,
+                        ex_map,
+                    )?;
                 }
             } else {
                 flattened_props.push(FlattenedPropEntry {
                     name: format!("{}{}", &prefix, &prop.name),
                     prop: prop.to_owned(),
                     array_elem_prop: if prop.raw_type == 5 {
-                        Some(send_table.properties[i - 1].to_owned())
+                        let elem = i
+                            .checked_sub(1)
+                            .and_then(|j| send_table.properties.get(j))
+                            .ok_or_else(|| {
+                                ParseError::malformed(format!(
+                                    "Array prop {} in send table {} has no element prop",
+                                    prop.name, send_table.name
+                                ))
+                            })?;
+                        Some(elem.to_owned())
                     } else {
                         None
                     },
@@ -961,6 +2057,7 @@ async fn gather_props_iterate(
             }
         }
     }
+    Ok(())
 }
 
 #[inline]
@@ -976,3 +2073,367 @@ fn is_prop_excluded(
     }
     false
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::Write, path::PathBuf};
+
+    use super::*;
+
+    fn header_bytes() -> Vec<u8> {
+        let mut bytes = b"HL2DEMO\0".to_vec();
+        bytes.extend_from_slice(&4_i32.to_le_bytes());
+        bytes.extend_from_slice(&13546_i32.to_le_bytes());
+        for field in ["GOTV Relay", "GOTV", "de_dust2", "csgo"] {
+            let mut padded = [0; 260];
+            padded[..field.len()].copy_from_slice(field.as_bytes());
+            bytes.extend_from_slice(&padded);
+        }
+        bytes.extend_from_slice(&1.0_f32.to_le_bytes());
+        bytes.extend_from_slice(&128_i32.to_le_bytes());
+        bytes.extend_from_slice(&2_i32.to_le_bytes());
+        bytes.extend_from_slice(&0_i32.to_le_bytes());
+        bytes
+    }
+
+    fn frame(command: PacketCommand, tick: i32) -> Vec<u8> {
+        let mut bytes = vec![command as u8];
+        bytes.extend_from_slice(&tick.to_le_bytes());
+        bytes.push(0);
+        bytes
+    }
+
+    fn varint(mut value: usize, bytes: &mut Vec<u8>) {
+        while value >= 0x80 {
+            bytes.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        bytes.push(value as u8);
+    }
+
+    fn packet(tick: i32, messages: &[(MessageType, Vec<u8>)]) -> Vec<u8> {
+        let mut chunk = Vec::new();
+        for (message_type, data) in messages {
+            varint(*message_type as usize, &mut chunk);
+            varint(data.len(), &mut chunk);
+            chunk.extend_from_slice(data);
+        }
+
+        let mut bytes = frame(PacketCommand::Packet, tick);
+        bytes.extend_from_slice(&[0; N_COMMAND_INFO_BITS / 8]);
+        bytes.extend_from_slice(&(chunk.len() as i32).to_le_bytes());
+        bytes.extend(chunk);
+        bytes
+    }
+
+    fn write_demo(name: &str, bytes: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("parser-{}-{name}.dem", std::process::id()));
+        fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn parse_many_returns_a_result_per_path() {
+        let mut bytes = header_bytes();
+        bytes.extend(frame(PacketCommand::Stop, 128));
+        let demo = write_demo("many", &bytes);
+        let missing = std::env::temp_dir().join("parser-missing.dem");
+
+        let mut setups = 0;
+        let results = Parser::parse_many(vec![demo.clone(), missing, demo.clone()], 2, |_| {
+            setups += 1
+        });
+        fs::remove_file(demo).unwrap();
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(setups, 2);
+        assert!(results[1].is_err());
+        for result in [&results[0], &results[2]] {
+            let parser = result.as_ref().unwrap();
+            assert_eq!(parser.ingame_tick, 128);
+            assert_eq!(parser.state.header.lock().map, "de_dust2");
+        }
+    }
+
+    #[test]
+    fn parse_errors_carry_tick_and_offset() {
+        let mut bytes = header_bytes();
+        bytes.extend(frame(PacketCommand::Sync, 64));
+        bytes.push(42);
+        let offset = bytes.len();
+        let demo = write_demo("unknown-command", &bytes);
+
+        let mut parser = Parser::new_from_file(&demo).unwrap();
+        fs::remove_file(demo).unwrap();
+        let err = parser.parse_to_end().unwrap_err();
+
+        assert!(matches!(
+            err,
+            ParseError::UnknownCommand { command: 42, .. }
+        ));
+        assert_eq!(err.tick(), Some(64));
+        assert_eq!(err.offset(), Some(offset));
+    }
+
+    #[test]
+    fn truncated_demos_end_cleanly() {
+        let mut bytes = header_bytes();
+        bytes.extend(frame(PacketCommand::Sync, 64));
+        bytes.extend(frame(PacketCommand::Sync, 65));
+        bytes.extend(&frame(PacketCommand::Sync, 66)[..3]);
+        let demo = write_demo("truncated", &bytes);
+
+        let mut parser = Parser::new_from_file(&demo).unwrap();
+        fs::remove_file(demo).unwrap();
+        parser.parse_to_end().unwrap();
+
+        assert!(parser.truncated);
+        assert_eq!(parser.last_good_tick, 65);
+    }
+
+    #[test]
+    fn seek_to_tick_stops_at_the_first_frame_past_the_target() {
+        let mut bytes = header_bytes();
+        for tick in [64, 96, 128] {
+            bytes.extend(frame(PacketCommand::Sync, tick));
+        }
+        bytes.extend(frame(PacketCommand::Stop, 160));
+        let demo = write_demo("seek", &bytes);
+
+        let mut parser = Parser::new_from_file(&demo).unwrap();
+        fs::remove_file(demo).unwrap();
+
+        assert!(parser.seek_to_tick(90).unwrap());
+        assert_eq!(parser.ingame_tick, 96);
+        assert!(!parser.seek_to_tick(1000).unwrap());
+        assert_eq!(parser.ingame_tick, 160);
+        assert!(!parser.truncated);
+    }
+
+    #[test]
+    fn server_timing_stats_cover_every_tick_message() {
+        let mut bytes = header_bytes();
+        for (tick, computation_time) in [(64, 1000), (65, 3000)] {
+            let msg = CnetMsgTick {
+                tick: tick as u32 + 100,
+                host_computationtime: Some(computation_time),
+                ..Default::default()
+            };
+            bytes.extend(packet(tick, &[(MessageType::Tick, msg.encode_to_vec())]));
+        }
+        bytes.extend(frame(PacketCommand::Stop, 66));
+        let demo = write_demo("timing", &bytes);
+
+        let mut parser = Parser::new_from_file(&demo).unwrap();
+        fs::remove_file(demo).unwrap();
+        parser.parse_to_end().unwrap();
+
+        let stats = &parser.timing_stats;
+        assert_eq!(stats.samples, 2);
+        assert_eq!(stats.replay_samples, 0);
+        assert_eq!(stats.first_server_tick, Some(164));
+        assert_eq!(stats.last_server_tick, Some(165));
+        assert_eq!(stats.max_host_computation_time, 3000);
+        assert_eq!(stats.mean_host_computation_time(), 2000.0);
+    }
+
+    #[test]
+    fn mistyped_event_keys_are_recorded_without_stopping_the_parse() {
+        let list = CsvcMsgGameEventList {
+            descriptors: vec![gameevent::DescriptorT {
+                eventid: 7,
+                name: "player_footstep".to_string(),
+                keys: vec![gameevent::DescriptorKeyT {
+                    r#type: 1,
+                    name: "userid".to_string(),
+                }],
+            }],
+        };
+        // userid arrives as a string, PlayerFootstep wants an integer.
+        let event = CsvcMsgGameEvent {
+            eventid: 7,
+            keys: vec![gameevent::KeyT {
+                r#type: 1,
+                val_string: "three".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let mut bytes = header_bytes();
+        bytes.extend(packet(
+            64,
+            &[(MessageType::GameEventList, list.encode_to_vec())],
+        ));
+        bytes.extend(packet(
+            65,
+            &[(MessageType::GameEvent, event.encode_to_vec())],
+        ));
+        bytes.extend(frame(PacketCommand::Sync, 66));
+        let demo = write_demo("mistyped-event-keys", &bytes);
+
+        let mut parser = Parser::new_from_file(&demo).unwrap();
+        fs::remove_file(demo).unwrap();
+        let untyped = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&untyped);
+        parser.on(move |event: &UntypedGameEvent, _| seen.lock().push(event.name.to_owned()));
+        parser.on(|_: &PlayerFootstep, _| panic!("the footstep can't be built"));
+        parser.parse_to_end().unwrap();
+
+        assert_eq!(*untyped.lock(), ["player_footstep"]);
+        assert_eq!(parser.event_errors.len(), 1);
+        assert_eq!(parser.event_errors[0].tick(), Some(65));
+        assert_eq!(parser.last_good_tick, 66);
+    }
+
+    #[test]
+    fn resuming_from_a_snapshot_emits_the_same_events_after_it() {
+        let list = CsvcMsgGameEventList {
+            descriptors: vec![gameevent::DescriptorT {
+                eventid: 7,
+                name: "player_footstep".to_string(),
+                keys: vec![gameevent::DescriptorKeyT {
+                    r#type: 4,
+                    name: "userid".to_string(),
+                }],
+            }],
+        };
+        let signon = packet(0, &[(MessageType::GameEventList, list.encode_to_vec())]);
+        let mut bytes = header_bytes();
+        let signon_len = bytes.len() - 4;
+        bytes[signon_len..].copy_from_slice(&(signon.len() as i32).to_le_bytes());
+        bytes.extend(signon);
+        for tick in 64..72 {
+            let event = CsvcMsgGameEvent {
+                eventid: 7,
+                keys: vec![gameevent::KeyT {
+                    r#type: 4,
+                    val_short: tick,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            };
+            bytes.extend(packet(
+                tick,
+                &[(MessageType::GameEvent, event.encode_to_vec())],
+            ));
+        }
+        bytes.extend(frame(PacketCommand::Stop, 72));
+        let demo = write_demo("resume", &bytes);
+
+        type Footsteps = Arc<Mutex<Vec<(isize, i32)>>>;
+        fn footsteps<R: Read + Seek + Send>(parser: &mut Parser<R>) -> Footsteps {
+            let seen = Footsteps::default();
+            let log = Arc::clone(&seen);
+            parser.on(move |e: &PlayerFootstep, ctx| log.lock().push((ctx.tick, e.userid)));
+            seen
+        }
+
+        let mut parser = Parser::new_from_file(&demo).unwrap();
+        parser.record_snapshots(SnapshotInterval::Ticks(3));
+        let all = footsteps(&mut parser);
+        parser.parse_to_end().unwrap();
+        let mid_demo = parser.snapshots.iter().find(|s| s.tick == 67).unwrap();
+        let snapshot = Snapshot::from_bytes(&mid_demo.to_bytes().unwrap()).unwrap();
+
+        let mut resumed = Parser::resume(fs::File::open(&demo).unwrap(), &snapshot).unwrap();
+        fs::remove_file(demo).unwrap();
+        let after = footsteps(&mut resumed);
+        resumed.parse_to_end().unwrap();
+
+        let expected: Vec<_> = all
+            .lock()
+            .iter()
+            .filter(|(tick, _)| *tick > snapshot.tick)
+            .copied()
+            .collect();
+        assert_eq!(*after.lock(), expected);
+        assert_eq!(expected.len(), 4);
+    }
+
+    #[test]
+    fn snapshots_keep_entity_runtime_fields() {
+        let mut bytes = header_bytes();
+        bytes.extend(frame(PacketCommand::Stop, 64));
+        let demo = write_demo("entity-runtime-fields", &bytes);
+
+        let mut server_class = Entity::default().server_class;
+        server_class.index = 0;
+        server_class.flattened_props = Some(Vec::new());
+        let position = |x| Vector64 {
+            x: Cow::Owned(x),
+            ..Vector64::default()
+        };
+        let entity = Entity {
+            server_class: server_class.to_owned(),
+            id: 5,
+            team: 3,
+            last_position: position(1.0),
+            is_in_buyzone: true,
+            buyzone_leave_pos: position(2.0),
+            last_flash_duration: 3.5,
+            current_flash_frame_agg: 4,
+            ..Entity::default()
+        };
+
+        let mut parser = Parser::new_from_file(&demo).unwrap();
+        parser.state.entities.lock().insert(5, entity);
+        let snapshot = parser.snapshot();
+
+        let mut restored = Parser::new_from_file(&demo).unwrap();
+        fs::remove_file(demo).unwrap();
+        restored.state.server_classes.lock().push(server_class);
+        restored.restore(&snapshot).unwrap();
+
+        let entities = restored.state.entities.lock();
+        let entity = &entities[&5];
+        assert_eq!(entity.team, 3);
+        assert_eq!(entity.last_position, position(1.0));
+        assert!(entity.is_in_buyzone);
+        assert_eq!(entity.buyzone_leave_pos, position(2.0));
+        assert_eq!(entity.last_flash_duration, 3.5);
+        assert_eq!(entity.current_flash_frame_agg, 4);
+    }
+
+    #[test]
+    fn string_table_history_references_are_bounds_checked() {
+        // One entry copying a byte from history slot 3 while the history is empty.
+        let msg = CsvcMsgCreateStringTable {
+            name: Some("userinfo".to_string()),
+            max_entries: Some(2),
+            num_entries: Some(1),
+            string_data: Some(vec![0x3e, 0x02, 0, 0, 0, 0, 0, 0]),
+            ..Default::default()
+        };
+        let mut bytes = header_bytes();
+        bytes.extend(packet(
+            64,
+            &[(MessageType::CreateStringTable, msg.encode_to_vec())],
+        ));
+        let demo = write_demo("string-table-history", &bytes);
+
+        let mut parser = Parser::new_from_file(&demo).unwrap();
+        fs::remove_file(demo).unwrap();
+        let err = parser.parse_to_end().unwrap_err();
+
+        assert!(matches!(err, ParseError::Malformed { .. }));
+        assert_eq!(err.tick(), Some(64));
+    }
+
+    #[test]
+    fn read_errors_are_not_taken_for_the_end_of_the_demo() {
+        let mut bytes = header_bytes();
+        bytes.extend(frame(PacketCommand::Sync, 64));
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        encoder.write_all(&bytes).unwrap();
+        let mut gzipped = encoder.finish().unwrap();
+        // Breaks the CRC32 in the gzip trailer.
+        let crc = gzipped.len() - 8;
+        gzipped[crc] ^= 0xff;
+        let demo = write_demo("corrupt-gzip", &gzipped);
+
+        let result = Parser::new_from_file(&demo).and_then(|mut parser| parser.parse_to_end());
+        fs::remove_file(demo).unwrap();
+
+        assert!(matches!(result, Err(ParseError::Io(_))));
+    }
+}
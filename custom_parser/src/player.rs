@@ -6,40 +6,13 @@ use custom_bitreader::BitReader;
 use crate::{
     common::Vector64,
     entity::Entity,
-    equipment::Equipment,
+    equipment::{Equipment, EQUIPMENT_ELEMENT_TO_NAME},
+    events::EventPlayer,
     serverclass::PropertyValueEnum,
     team::{Team, TeamState},
-    ENTITIES, GUID_LENGTH, PLAYERS_BY_ENTITY_ID, PLAYERS_BY_STEAM_ID, PLAYERS_BY_USER_ID,
-    PLAYER_NAME_MAX_LENGTH, RAW_PLAYERS, SERVER_CLASSES, SERVER_CLASSES_BY_NAME,
+    ParserState, GUID_LENGTH, PLAYER_NAME_MAX_LENGTH,
 };
 
-pub trait IsPlayer {
-    fn get_name(&self) -> &str;
-    fn get_user_id(&self) -> u32;
-    fn get_entity_id(&self) -> i32;
-}
-
-macro_rules! impl_player {
-    ($name:ident) => {
-        impl IsPlayer for $name {
-            fn get_name(&self) -> &str {
-                &self.name
-            }
-
-            fn get_user_id(&self) -> u32 {
-                self.user_id
-            }
-
-            fn get_entity_id(&self) -> i32 {
-                self.entity_id
-            }
-        }
-    };
-}
-
-impl_player!(Player);
-impl_player!(PlayerInfo);
-
 #[derive(Clone)]
 pub struct Player {
     pub steam_id: u64,
@@ -63,8 +36,8 @@ pub struct Player {
 }
 
 impl Player {
-    pub async fn _is_alive(&self) -> bool {
-        if let Some(entity) = ENTITIES.lock().await.get(&self.entity_id) {
+    pub fn _is_alive(&self, state: &ParserState) -> bool {
+        if let Some(entity) = state.entities.lock().get(&self.entity_id) {
             if let PropertyValueEnum::Integer(Cow::Owned(health)) =
                 entity._property_value_must("m_iHealth")
             {
@@ -157,32 +130,33 @@ fn uint32(b: Vec<u8>) -> u32 {
     b[3] as u32 | (b[2] as u32) << 8 | (b[1] as u32) << 16 | (b[0] as u32) << 24
 }
 
-pub async fn bind_players() {
-    SERVER_CLASSES.lock().await[SERVER_CLASSES_BY_NAME.lock().await["DT_CSPlayer"].index as usize]
-        .created_handlers = Some(vec![|id| {
-        Box::pin(async move {
-            bind_new_player(id).await;
-        })
-    }])
+pub fn bind_players(state: &ParserState) {
+    let index = match state.server_classes_by_name.lock().get("DT_CSPlayer") {
+        Some(server_class) => server_class.index as usize,
+        None => return,
+    };
+    if let Some(server_class) = state.server_classes.lock().get_mut(index) {
+        server_class.created_handlers = Some(vec![bind_new_player]);
+    }
 }
 
-async fn bind_new_player(entity_id: i32) {
-    create_or_update_player(entity_id).await;
+fn bind_new_player(state: &ParserState, entity: &Entity) {
+    create_or_update_player(state, entity.id);
 }
 
-async fn index_player_by_steam_id(pl: &Player) {
+fn index_player_by_steam_id(state: &ParserState, pl: &Player) {
     if pl.is_bot && pl.steam_id > 0 {
-        PLAYERS_BY_STEAM_ID
+        state
+            .players_by_steam_id
             .lock()
-            .await
             .insert(pl.steam_id, pl.to_owned());
     }
 }
 
-async fn create_or_update_player(entity_id: i32) {
-    let raw_players = RAW_PLAYERS.lock().await;
-    let mut players_by_entity_id = PLAYERS_BY_ENTITY_ID.lock().await;
-    let mut players_by_user_id = PLAYERS_BY_USER_ID.lock().await;
+fn create_or_update_player(state: &ParserState, entity_id: i32) {
+    let raw_players = state.raw_players.lock();
+    let mut players_by_entity_id = state.players_by_entity_id.lock();
+    let mut players_by_user_id = state.players_by_user_id.lock();
 
     let player = players_by_entity_id.get_mut(&entity_id);
     let found_player: Player;
@@ -191,7 +165,7 @@ async fn create_or_update_player(entity_id: i32) {
             pl.entity_id = entity_id;
             pl.is_connected = true;
             found_player = pl.to_owned();
-            index_player_by_steam_id(pl).await;
+            index_player_by_steam_id(state, pl);
         }
         None => match raw_players.get(&(entity_id - 1)) {
             Some(rp) => match players_by_user_id.get_mut(&rp.user_id) {
@@ -200,7 +174,7 @@ async fn create_or_update_player(entity_id: i32) {
                     pl.is_connected = true;
                     players_by_entity_id.insert(entity_id, pl.to_owned());
                     found_player = pl.to_owned();
-                    index_player_by_steam_id(pl).await;
+                    index_player_by_steam_id(state, pl);
                 }
                 None => {
                     let player = Player {
@@ -226,7 +200,7 @@ async fn create_or_update_player(entity_id: i32) {
                         is_unknown: false,
                     };
 
-                    index_player_by_steam_id(&player).await;
+                    index_player_by_steam_id(state, &player);
                     players_by_entity_id.insert(entity_id, player.to_owned());
                     found_player = player;
                 }
@@ -265,3 +239,61 @@ async fn create_or_update_player(entity_id: i32) {
         players_by_user_id.insert(rp.user_id, found_player);
     }
 }
+
+// Looks a game event's userid up in the player tables and reads where the player stood and
+// what they were holding from their entity. A userid of 0 refers to the world.
+pub fn resolve_event_player(state: &ParserState, user_id: i32) -> Option<EventPlayer> {
+    if user_id == 0 {
+        return None;
+    }
+
+    let info = state
+        .player_info_by_user_id
+        .lock()
+        .get(&user_id)
+        .map(|info| (info.name.to_owned(), info.xuid, info.entity_id));
+    let (name, steam_id, entity_id) = match info {
+        Some(info) => info,
+        None => state
+            .players_by_user_id
+            .lock()
+            .get(&(user_id as u32))
+            .map(|pl| (pl.name.to_owned(), pl.steam_id, pl.entity_id))?,
+    };
+
+    let mut player = EventPlayer {
+        user_id,
+        name,
+        steam_id,
+        ..Default::default()
+    };
+
+    let weapon_class = {
+        let entities = state.entities.lock();
+        let entity = match entities.get(&entity_id) {
+            Some(entity) => entity,
+            None => return Some(player),
+        };
+
+        let position = entity.get_position();
+        player.team = entity.team as i32;
+        player.x = *position.x;
+        player.y = *position.y;
+        player.z = *position.z;
+
+        entities
+            .get(&entity._active_weapon_id())
+            .map(|weapon| weapon.server_class.id)
+    };
+
+    if let Some(class_id) = weapon_class {
+        if let Some(eq_type) = state.equipment_mapping.lock().get(&class_id) {
+            player.active_weapon = *eq_type;
+            player.active_weapon_name = EQUIPMENT_ELEMENT_TO_NAME
+                .get(eq_type)
+                .map_or_else(String::new, |name| name.to_string());
+        }
+    }
+
+    Some(player)
+}
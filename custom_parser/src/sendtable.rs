@@ -66,9 +66,7 @@ impl From<&SendpropT> for SendTableProperty {
 
 #[derive(Debug, Clone)]
 pub struct ExcludeEntry {
-    pub var_name: String,
     pub dt_name: String,
-    pub excluding_dt: String,
 }
 
 
@@ -1,6 +1,10 @@
 pub mod string_float_serializer {
-    use serde::{de, Deserialize, Deserializer, Serializer};
-    use serde_json::Value;
+    use std::fmt;
+
+    use serde::{
+        de::{self, Visitor},
+        Deserializer, Serializer,
+    };
 
     pub fn serialize<S>(float: &f64, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -13,10 +17,32 @@ pub mod string_float_serializer {
     where
         D: Deserializer<'de>,
     {
-        Ok(match Value::deserialize(deserializer)? {
-            Value::String(s) => s.parse().map_err(de::Error::custom)?,
-            Value::Number(num) => num.as_f64().ok_or(de::Error::custom("Invalid number"))?,
-            _ => return Err(de::Error::custom("wrong type")),
-        })
+        deserializer.deserialize_any(StringFloatVisitor)
+    }
+
+    struct StringFloatVisitor;
+
+    impl<'de> Visitor<'de> for StringFloatVisitor {
+        type Value = f64;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a number or a string holding one")
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<f64, E> {
+            v.parse().map_err(de::Error::custom)
+        }
+
+        fn visit_f64<E: de::Error>(self, v: f64) -> Result<f64, E> {
+            Ok(v)
+        }
+
+        fn visit_i64<E: de::Error>(self, v: i64) -> Result<f64, E> {
+            Ok(v as f64)
+        }
+
+        fn visit_u64<E: de::Error>(self, v: u64) -> Result<f64, E> {
+            Ok(v as f64)
+        }
     }
 }
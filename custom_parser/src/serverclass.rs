@@ -1,20 +1,23 @@
 use std::{
     borrow::Cow,
     io::{Cursor, Read, Seek},
-    pin::Pin,
+    sync::Arc,
 };
 
 use ahash::AHashMap as HashMap;
 use custom_bitreader::BitReader;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     common::Vector64,
     entity::{Entity, Property},
     sendtable::SendTableProperty,
-    INGAME_TICK,
+    snapshot::EntitySnapshot,
+    ParseError, ParserState,
 };
 
-type CreateHandler = fn(i32) -> Pin<Box<(dyn futures::Future<Output = ()> + Send + Sync)>>;
+// Runs once a new entity of the class has its initial props.
+type CreateHandler = fn(&ParserState, &Entity);
 
 #[derive(Clone)]
 pub struct ServerClass {
@@ -40,13 +43,19 @@ impl ServerClass {
     }
 
     #[inline]
-    pub fn new<T: Read + Seek + Send>(i: usize, r: &mut BitReader<T>, max: usize) -> Self {
+    pub fn new<T: Read + Seek + Send>(
+        i: usize,
+        r: &mut BitReader<T>,
+        max: usize,
+    ) -> Result<Self, ParseError> {
         let class_id = r.read_int(16);
         if class_id > max {
-            panic!("Invalid class index");
+            return Err(ParseError::malformed(format!(
+                "Invalid class index {class_id}"
+            )));
         }
 
-        Self {
+        Ok(Self {
             id: class_id as i32,
             name: r.read_string(),
             dt_id: -1,
@@ -61,15 +70,10 @@ impl ServerClass {
             created_handlers: None,
 
             index: i as i32,
-        }
+        })
     }
 
-    pub async fn new_entity<T: Read + Seek + Send>(
-        &mut self,
-        r: &mut BitReader<T>,
-        id: i32,
-        prop_indices_vec: &mut Vec<u32>,
-    ) -> Entity {
+    pub(crate) fn blank_entity(&self, id: i32, created_on_tick: isize) -> Entity {
         let mut entity = Entity {
             server_class: self.to_owned(),
             id,
@@ -84,7 +88,7 @@ impl ServerClass {
             wep_prefix: Cow::Borrowed(""),
             weapon_cache: None,
             position_history: HashMap::new(),
-            created_on_tick: *INGAME_TICK.lock().await,
+            created_on_tick,
             is_in_buyzone: false,
             buyzone_leave_pos: Vector64::default(),
             team: 0,
@@ -103,11 +107,23 @@ impl ServerClass {
 
         Entity::initialize(&mut entity);
 
+        entity
+    }
+
+    pub fn new_entity<T: Read + Seek + Send>(
+        &mut self,
+        state: &Arc<ParserState>,
+        r: &mut BitReader<T>,
+        id: i32,
+        prop_indices_vec: &mut Vec<u32>,
+    ) -> Result<Entity, ParseError> {
+        let mut entity = self.blank_entity(id, *state.ingame_tick.lock());
+
         if let Some(false) = self.preprocessed_baseline_is_empty() {
             entity.apply_baseline();
         } else if let Some(instance_baseline) = &mut self.instance_baseline {
             let mut _r = BitReader::new_small_bit_reader(Cursor::new(instance_baseline));
-            entity.apply_update(&mut _r, prop_indices_vec).await;
+            entity.apply_update(state, &mut _r, prop_indices_vec)?;
 
             let mut ppb: HashMap<i32, PropertyValueEnum> =
                 HashMap::with_capacity(entity.props.len());
@@ -123,15 +139,50 @@ impl ServerClass {
             self.clear_preprocessed_baseline();
         }
 
-        entity.apply_update(r, prop_indices_vec).await;
+        entity.apply_update(state, r, prop_indices_vec)?;
+
+        self.run_created_handlers(state, &entity);
+
+        Ok(entity)
+    }
+
+    pub fn restore_entity(
+        &self,
+        state: &Arc<ParserState>,
+        snapshot: &EntitySnapshot,
+    ) -> Result<Entity, ParseError> {
+        let mut entity = self.blank_entity(snapshot.id, snapshot.created_on_tick);
+        if entity.props.len() != snapshot.props.len() {
+            return Err(ParseError::malformed(format!(
+                "Snapshot of entity {} has {} props, server class {} has {}",
+                snapshot.id,
+                snapshot.props.len(),
+                self.name,
+                entity.props.len()
+            )));
+        }
+
+        for (prop, value) in entity.props.iter_mut().zip(&snapshot.props) {
+            prop.value = value.to_owned();
+        }
+        entity.team = snapshot.team;
+        entity.last_position = snapshot.last_position.to_owned();
+        entity.is_in_buyzone = snapshot.is_in_buyzone;
+        entity.buyzone_leave_pos = snapshot.buyzone_leave_pos.to_owned();
+        entity.last_flash_duration = snapshot.last_flash_duration;
+        entity.current_flash_frame_agg = snapshot.current_flash_frame_agg;
+
+        self.run_created_handlers(state, &entity);
+
+        Ok(entity)
+    }
 
+    fn run_created_handlers(&self, state: &Arc<ParserState>, entity: &Entity) {
         if let Some(created_handlers) = &self.created_handlers {
             for h in created_handlers {
-                h(entity.id).await;
+                h(state, entity);
             }
         }
-
-        entity
     }
 
     fn clear_preprocessed_baseline(&mut self) {
@@ -184,7 +235,7 @@ impl Default for FlattenedPropEntry {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum PropertyValueEnum {
     Array(Vec<PropertyValueEnum>),
     Vector(Cow<'static, Vector64>),
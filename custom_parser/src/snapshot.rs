@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{common::Vector64, equipment::Equipment, serverclass::PropertyValueEnum};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SnapshotInterval {
+    Ticks(isize),
+    RoundStart,
+}
+
+// Everything needed to pick a demo back up at `tick` without decoding what came before it,
+// apart from the signon data at the start of the demo.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub tick: isize,
+    pub round: i32,
+    // Byte offset of the first frame after `tick`.
+    pub offset: usize,
+    pub string_table_entries: Vec<StringTableEntry>,
+    pub entities: Vec<EntitySnapshot>,
+    pub grenade_projectiles: Vec<GrenadeProjectileSnapshot>,
+    // Grenades in flight, by the entity id of the player who threw them.
+    pub thrown_grenades: Vec<(i32, Vec<EquipmentSnapshot>)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StringTableEntry {
+    pub table: String,
+    pub index: i32,
+    pub entry: String,
+    pub user_data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntitySnapshot {
+    pub id: i32,
+    pub server_class_index: i32,
+    pub created_on_tick: isize,
+    // One value per flattened prop of the server class, in the same order.
+    pub props: Vec<PropertyValueEnum>,
+    // What the parser tracks on top of the props. `position_history` isn't kept, it starts
+    // over at the snapshot tick.
+    pub team: u8,
+    pub last_position: Vector64,
+    pub is_in_buyzone: bool,
+    pub buyzone_leave_pos: Vector64,
+    pub last_flash_duration: f64,
+    pub current_flash_frame_agg: u64,
+}
+
+// What a grenade projectile picked up while flying. Its thrower and owner come from its
+// entity again on restore.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrenadeProjectileSnapshot {
+    pub entity_id: i32,
+    pub wep_type: i32,
+    pub trajectory: Vec<Vector64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EquipmentSnapshot {
+    pub eq_type: i32,
+    pub entity_id: i32,
+    pub owner_entity_id: i32,
+    pub original_string: String,
+    pub landed: bool,
+}
+
+impl From<&Equipment> for EquipmentSnapshot {
+    fn from(equipment: &Equipment) -> Self {
+        EquipmentSnapshot {
+            eq_type: equipment.eq_type,
+            entity_id: equipment.entity_id,
+            owner_entity_id: equipment.owner_entity_id,
+            original_string: equipment.original_string.to_owned(),
+            landed: equipment.landed,
+        }
+    }
+}
+
+impl From<&EquipmentSnapshot> for Equipment {
+    fn from(snapshot: &EquipmentSnapshot) -> Self {
+        Equipment {
+            eq_type: snapshot.eq_type,
+            entity_id: snapshot.entity_id,
+            owner_entity_id: snapshot.owner_entity_id,
+            original_string: snapshot.original_string.to_owned(),
+            landed: snapshot.landed,
+            ..Default::default()
+        }
+    }
+}
+
+impl Snapshot {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, bincode::Error> {
+        bincode::serialize(self)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, bincode::Error> {
+        bincode::deserialize(bytes)
+    }
+}
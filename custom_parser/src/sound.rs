@@ -0,0 +1,221 @@
+use std::borrow::Cow;
+
+use prost::Message;
+use serde::{Deserialize, Serialize};
+
+use crate::common::Vector64;
+
+const DEFAULT_SOUND_VOLUME: u32 = 127;
+const DEFAULT_SOUND_PITCH: i32 = 100;
+const DEFAULT_SOUND_LEVEL: i32 = 75;
+const DEFAULT_SOUND_CHANNEL: i32 = 6;
+
+#[derive(Clone, PartialEq, Message)]
+pub struct CsvcMsgSounds {
+    #[prost(bool, optional, tag = "1")]
+    pub reliable_sound: Option<bool>,
+    #[prost(message, repeated, tag = "2")]
+    pub sounds: Vec<SoundData>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct SoundData {
+    #[prost(sint32, optional, tag = "1")]
+    pub origin_x: Option<i32>,
+    #[prost(sint32, optional, tag = "2")]
+    pub origin_y: Option<i32>,
+    #[prost(sint32, optional, tag = "3")]
+    pub origin_z: Option<i32>,
+    #[prost(uint32, optional, tag = "4")]
+    pub volume: Option<u32>,
+    #[prost(float, optional, tag = "5")]
+    pub delay_value: Option<f32>,
+    #[prost(int32, optional, tag = "6")]
+    pub sequence_number: Option<i32>,
+    #[prost(int32, optional, tag = "7")]
+    pub entity_index: Option<i32>,
+    #[prost(int32, optional, tag = "8")]
+    pub channel: Option<i32>,
+    #[prost(int32, optional, tag = "9")]
+    pub pitch: Option<i32>,
+    #[prost(int32, optional, tag = "10")]
+    pub flags: Option<i32>,
+    #[prost(uint32, optional, tag = "11")]
+    pub sound_num: Option<u32>,
+    #[prost(fixed32, optional, tag = "12")]
+    pub sound_num_handle: Option<u32>,
+    #[prost(int32, optional, tag = "13")]
+    pub speaker_entity: Option<i32>,
+    #[prost(int32, optional, tag = "14")]
+    pub random_seed: Option<i32>,
+    #[prost(int32, optional, tag = "15")]
+    pub sound_level: Option<i32>,
+    #[prost(bool, optional, tag = "16")]
+    pub is_sentence: Option<bool>,
+    #[prost(bool, optional, tag = "17")]
+    pub is_ambient: Option<bool>,
+}
+
+// A sound the server told clients to play, as emitted on the "sound" channel.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Sound {
+    pub tick: isize,
+    pub reliable: bool,
+    pub entity_index: i32,
+    pub sound_num: u32,
+    pub sound_num_handle: u32,
+    // The soundprecache entry of `sound_num`, when there is one.
+    pub sound_name: Option<String>,
+    pub origin: Vector64,
+    // 0 to 127.
+    pub volume: u32,
+    pub delay: f32,
+    pub sequence_number: i32,
+    pub channel: i32,
+    pub pitch: i32,
+    pub flags: i32,
+    pub speaker_entity: i32,
+    pub random_seed: i32,
+    pub sound_level: i32,
+    pub is_sentence: bool,
+    pub is_ambient: bool,
+}
+
+impl Sound {
+    fn new(tick: isize, reliable: bool) -> Self {
+        Sound {
+            tick,
+            reliable,
+            entity_index: 0,
+            sound_num: 0,
+            sound_num_handle: 0,
+            sound_name: None,
+            origin: Vector64::default(),
+            volume: DEFAULT_SOUND_VOLUME,
+            delay: 0.0,
+            sequence_number: 0,
+            channel: DEFAULT_SOUND_CHANNEL,
+            pitch: DEFAULT_SOUND_PITCH,
+            flags: 0,
+            speaker_entity: -1,
+            random_seed: 0,
+            sound_level: DEFAULT_SOUND_LEVEL,
+            is_sentence: false,
+            is_ambient: false,
+        }
+    }
+
+    // Overwrites what `data` carries. Everything else stays as the previous sound of the
+    // message had it.
+    fn apply(&mut self, data: &SoundData) {
+        if let Some(x) = data.origin_x {
+            self.origin.x = Cow::Owned(x as f64);
+        }
+        if let Some(y) = data.origin_y {
+            self.origin.y = Cow::Owned(y as f64);
+        }
+        if let Some(z) = data.origin_z {
+            self.origin.z = Cow::Owned(z as f64);
+        }
+
+        macro_rules! apply_fields {
+            ($($field:ident <- $source:ident),* $(,)?) => {
+                $(
+                    if let Some(value) = data.$source {
+                        self.$field = value;
+                    }
+                )*
+            };
+        }
+        apply_fields!(
+            volume <- volume,
+            delay <- delay_value,
+            sequence_number <- sequence_number,
+            entity_index <- entity_index,
+            channel <- channel,
+            pitch <- pitch,
+            flags <- flags,
+            sound_num <- sound_num,
+            sound_num_handle <- sound_num_handle,
+            speaker_entity <- speaker_entity,
+            random_seed <- random_seed,
+            sound_level <- sound_level,
+            is_sentence <- is_sentence,
+            is_ambient <- is_ambient,
+        );
+    }
+}
+
+// Sounds are delta encoded against the one before them in the message. `sound_name` looks
+// a sound number up in the sound precache.
+pub fn read_sounds<F>(msg: &CsvcMsgSounds, tick: isize, sound_name: F) -> Vec<Sound>
+where
+    F: Fn(u32) -> Option<String>,
+{
+    let mut sound = Sound::new(tick, msg.reliable_sound());
+    msg.sounds
+        .iter()
+        .map(|data| {
+            sound.apply(data);
+            sound.sound_name = sound_name(sound.sound_num);
+            sound.clone()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_sounds_applies_deltas_in_order() {
+        let msg = CsvcMsgSounds {
+            reliable_sound: Some(true),
+            sounds: vec![
+                SoundData {
+                    origin_x: Some(100),
+                    origin_y: Some(-200),
+                    origin_z: Some(30),
+                    entity_index: Some(5),
+                    sound_num: Some(12),
+                    pitch: Some(90),
+                    ..Default::default()
+                },
+                SoundData {
+                    origin_x: Some(110),
+                    sound_num: Some(13),
+                    volume: Some(64),
+                    ..Default::default()
+                },
+            ],
+        };
+
+        let sounds = read_sounds(&msg, 321, |num| (num == 12).then(|| "step.wav".to_string()));
+        assert_eq!(sounds.len(), 2);
+
+        let first = &sounds[0];
+        assert_eq!((first.tick, first.reliable), (321, true));
+        assert_eq!(first.sound_name.as_deref(), Some("step.wav"));
+        assert_eq!(first.volume, DEFAULT_SOUND_VOLUME);
+        assert_eq!(first.channel, DEFAULT_SOUND_CHANNEL);
+        assert_eq!(first.sound_level, DEFAULT_SOUND_LEVEL);
+        assert_eq!(first.speaker_entity, -1);
+
+        // Anything the second sound doesn't send carries over from the first.
+        let second = &sounds[1];
+        assert_eq!(second.sound_num, 13);
+        assert_eq!(second.sound_name, None);
+        assert_eq!(second.volume, 64);
+        assert_eq!((second.entity_index, second.pitch), (5, 90));
+        assert_eq!(
+            (*second.origin.x, *second.origin.y, *second.origin.z),
+            (110.0, -200.0, 30.0)
+        );
+    }
+
+    #[test]
+    fn read_sounds_of_an_empty_message() {
+        let sounds = read_sounds(&CsvcMsgSounds::default(), 0, |_| None);
+        assert!(sounds.is_empty());
+    }
+}
@@ -0,0 +1,183 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, ErrorKind, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use bzip2::read::BzDecoder;
+use custom_bitreader::ForwardReader;
+use flate2::read::{DeflateDecoder, MultiGzDecoder};
+
+const MAGIC_GZIP: &[u8] = &[0x1f, 0x8b];
+const MAGIC_BZIP2: &[u8] = b"BZh";
+const MAGIC_ZSTD: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+const MAGIC_ZIP: &[u8] = b"PK\x03\x04";
+
+const ZIP_LOCAL_HEADER_LENGTH: usize = 30;
+const ZIP_FLAG_ENCRYPTED: u16 = 1;
+const ZIP_FLAG_DATA_DESCRIPTOR: u16 = 1 << 3;
+const ZIP_METHOD_STORED: u16 = 0;
+const ZIP_METHOD_DEFLATE: u16 = 8;
+const ZIP_METHOD_BZIP2: u16 = 12;
+const ZIP_METHOD_ZSTD: u16 = 93;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Bzip2,
+    Zstd,
+    Zip,
+}
+
+impl Compression {
+    pub fn detect(magic: &[u8]) -> Self {
+        if magic.starts_with(MAGIC_GZIP) {
+            Compression::Gzip
+        } else if magic.starts_with(MAGIC_BZIP2) {
+            Compression::Bzip2
+        } else if magic.starts_with(MAGIC_ZSTD) {
+            Compression::Zstd
+        } else if magic.starts_with(MAGIC_ZIP) {
+            Compression::Zip
+        } else {
+            Compression::None
+        }
+    }
+}
+
+// A demo file, decompressed on the fly when needed. Plain demos stay seekable; compressed
+// ones are read front to back.
+pub enum DemoSource {
+    Plain(BufReader<File>),
+    Compressed(Compression, ForwardReader<Box<dyn Read + Send>>),
+}
+
+impl DemoSource {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let compression = Compression::detect(reader.fill_buf()?);
+
+        let decoder: Box<dyn Read + Send> = match compression {
+            Compression::None => return Ok(DemoSource::Plain(reader)),
+            Compression::Gzip => Box::new(MultiGzDecoder::new(reader)),
+            Compression::Bzip2 => Box::new(BzDecoder::new(reader)),
+            Compression::Zstd => Box::new(zstd::Decoder::with_buffer(reader)?),
+            Compression::Zip => zip_entry(reader)?,
+        };
+
+        Ok(DemoSource::Compressed(
+            compression,
+            ForwardReader::new(decoder),
+        ))
+    }
+
+    pub fn compression(&self) -> Compression {
+        match self {
+            DemoSource::Plain(_) => Compression::None,
+            DemoSource::Compressed(compression, _) => *compression,
+        }
+    }
+}
+
+impl Read for DemoSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            DemoSource::Plain(r) => r.read(buf),
+            DemoSource::Compressed(_, r) => r.read(buf),
+        }
+    }
+}
+
+impl Seek for DemoSource {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            DemoSource::Plain(r) => r.seek(pos),
+            DemoSource::Compressed(_, r) => r.seek(pos),
+        }
+    }
+
+    fn stream_position(&mut self) -> io::Result<u64> {
+        match self {
+            DemoSource::Plain(r) => r.stream_position(),
+            DemoSource::Compressed(_, r) => r.stream_position(),
+        }
+    }
+}
+
+// Streams the first entry of a zip archive straight from its local file header, without
+// going through the central directory at the end of the file.
+fn zip_entry(mut reader: BufReader<File>) -> io::Result<Box<dyn Read + Send>> {
+    let mut header = [0; ZIP_LOCAL_HEADER_LENGTH];
+    reader.read_exact(&mut header)?;
+
+    let u16_at = |i: usize| u16::from_le_bytes([header[i], header[i + 1]]);
+    let u32_at =
+        |i: usize| u32::from_le_bytes([header[i], header[i + 1], header[i + 2], header[i + 3]]);
+
+    let flags = u16_at(6);
+    let method = u16_at(8);
+    let compressed_size = u32_at(18) as u64;
+    let name_length = u16_at(26) as u64;
+    let extra_length = u16_at(28) as u64;
+
+    if flags & ZIP_FLAG_ENCRYPTED != 0 {
+        return Err(invalid_data("encrypted zip entries are not supported"));
+    }
+
+    io::copy(
+        &mut (&mut reader).take(name_length + extra_length),
+        &mut io::sink(),
+    )?;
+
+    // With a data descriptor the sizes are only written after the data, so the entry has to
+    // be delimited by its own compressed stream.
+    let sized = flags & ZIP_FLAG_DATA_DESCRIPTOR == 0;
+    let data: Box<dyn Read + Send> = if sized {
+        Box::new(reader.take(compressed_size))
+    } else {
+        Box::new(reader)
+    };
+
+    match method {
+        ZIP_METHOD_STORED if sized => Ok(data),
+        ZIP_METHOD_DEFLATE => Ok(Box::new(DeflateDecoder::new(data))),
+        ZIP_METHOD_BZIP2 => Ok(Box::new(BzDecoder::new(data))),
+        ZIP_METHOD_ZSTD => Ok(Box::new(zstd::Decoder::new(data)?)),
+        _ => Err(invalid_data(format!(
+            "unsupported zip compression method {method}"
+        ))),
+    }
+}
+
+fn invalid_data<S: Into<String>>(reason: S) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, reason.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_recognises_magic_bytes() {
+        assert_eq!(
+            Compression::detect(&[0x1f, 0x8b, 0x08, 0x00]),
+            Compression::Gzip
+        );
+        assert_eq!(Compression::detect(b"BZh91AY&SY"), Compression::Bzip2);
+        assert_eq!(
+            Compression::detect(&[0x28, 0xb5, 0x2f, 0xfd, 0x04]),
+            Compression::Zstd
+        );
+        assert_eq!(Compression::detect(b"PK\x03\x04\x14\x00"), Compression::Zip);
+        assert_eq!(Compression::detect(b"HL2DEMO\0"), Compression::None);
+    }
+
+    #[test]
+    fn detect_needs_the_whole_magic() {
+        assert_eq!(Compression::detect(&[]), Compression::None);
+        assert_eq!(Compression::detect(&[0x1f]), Compression::None);
+        assert_eq!(Compression::detect(&[0x28, 0xb5, 0x2f]), Compression::None);
+        assert_eq!(Compression::detect(b"PK\x05\x06"), Compression::None);
+    }
+}
@@ -1,34 +1,53 @@
 use ahash::AHashMap as HashMap;
-use lazy_static::lazy_static;
-use parking_lot::Mutex;
 
-use crate::{entity::Entity, player::PlayerInfo};
+use std::borrow::Cow;
+
+use crate::{entity::Entity, player::PlayerInfo, serverclass::PropertyValueEnum, ParserState};
 
 pub type Team = u8;
 
-lazy_static! {
-    pub static ref T_STATE: Mutex<TeamState> = Mutex::new(TeamState {
-        team: 2,
-        entity: None,
-        members: HashMap::with_capacity(7),
-        player_ids: Vec::with_capacity(7),
-    });
-    pub static ref CT_STATE: Mutex<TeamState> = Mutex::new(TeamState {
-        team: 3,
-        entity: None,
-        members: HashMap::with_capacity(7),
-        player_ids: Vec::with_capacity(7),
-    });
-}
+pub const TEAM_TERRORISTS: Team = 2;
+pub const TEAM_COUNTER_TERRORISTS: Team = 3;
 
 #[derive(Clone, Default)]
 pub struct TeamState {
     pub team: Team,
-    pub entity: Option<Entity>,
+    // The DT_CSTeam entity of the team, once the server created it.
+    pub entity_id: Option<i32>,
     pub members: HashMap<i32, PlayerInfo>,
     pub player_ids: Vec<u8>,
 }
 
-pub async fn bind_team_states() {
-    todo!()
+impl TeamState {
+    pub fn new(team: Team) -> Self {
+        Self {
+            team,
+            entity_id: None,
+            members: HashMap::with_capacity(7),
+            player_ids: Vec::with_capacity(7),
+        }
+    }
+}
+
+pub fn bind_team_states(state: &ParserState) {
+    let index = match state.server_classes_by_name.lock().get("DT_CSTeam") {
+        Some(server_class) => server_class.index as usize,
+        None => return,
+    };
+    if let Some(server_class) = state.server_classes.lock().get_mut(index) {
+        server_class.created_handlers = Some(vec![bind_team]);
+    }
+}
+
+fn bind_team(state: &ParserState, entity: &Entity) {
+    let team = match entity.property("m_iTeamNum").map(|prop| prop.value) {
+        Some(PropertyValueEnum::Integer(Cow::Owned(team))) => team as Team,
+        _ => return,
+    };
+
+    match team {
+        TEAM_TERRORISTS => state.t_state.lock().entity_id = Some(entity.id),
+        TEAM_COUNTER_TERRORISTS => state.ct_state.lock().entity_id = Some(entity.id),
+        _ => {}
+    }
 }
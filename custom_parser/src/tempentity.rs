@@ -0,0 +1,286 @@
+use std::{borrow::Cow, io::Cursor, sync::Arc};
+
+use ahash::AHashMap as HashMap;
+use custom_bitreader::BitReader;
+use prost::Message;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::Vector64,
+    entity::Entity,
+    serverclass::{PropertyValueEnum, ServerClass},
+    ParseError, ParserState,
+};
+
+const TEMP_ENTITY_DELAY_BITS: usize = 8;
+const TEMP_ENTITY_ORIGIN_PROP: &str = "m_vecOrigin";
+const TEMP_ENTITY_EFFECT_ORIGIN_PROPS: [&str; 3] = [
+    "m_EffectData.m_vOrigin.x",
+    "m_EffectData.m_vOrigin.y",
+    "m_EffectData.m_vOrigin.z",
+];
+
+#[derive(Clone, PartialEq, Eq, Message)]
+pub struct CsvcMsgTempEntities {
+    #[prost(bool, optional, tag = "1")]
+    pub reliable: Option<bool>,
+    #[prost(int32, optional, tag = "2")]
+    pub num_entries: Option<i32>,
+    #[prost(bytes = "vec", optional, tag = "3")]
+    pub entity_data: Option<Vec<u8>>,
+}
+
+// A decoded temp entity (CTEFireBullets, CTEEffectDispatch, CTEBloodSprite ...), as emitted on
+// the "temp_entity" channel. Only the props sent for it are in `props`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TempEntity {
+    pub tick: isize,
+    // Seconds the client waits before playing the effect.
+    pub delay: f32,
+    pub class_id: i32,
+    pub class_name: String,
+    pub props: HashMap<String, PropertyValueEnum>,
+}
+
+impl TempEntity {
+    pub fn prop(&self, name: &str) -> Option<&PropertyValueEnum> {
+        self.props.get(name)
+    }
+
+    // Where the effect happens, for the classes that carry an origin.
+    pub fn origin(&self) -> Option<Vector64> {
+        if let Some(PropertyValueEnum::Vector(origin)) = self.prop(TEMP_ENTITY_ORIGIN_PROP) {
+            return Some(origin.clone().into_owned());
+        }
+
+        let mut coords = TEMP_ENTITY_EFFECT_ORIGIN_PROPS
+            .iter()
+            .map(|name| match self.prop(name) {
+                Some(PropertyValueEnum::Float(v)) => Some(**v),
+                _ => None,
+            });
+        Some(Vector64 {
+            x: Cow::Owned(coords.next()??),
+            y: Cow::Owned(coords.next()??),
+            z: Cow::Owned(coords.next()??),
+        })
+    }
+
+    fn from_entity(entity: &Entity, tick: isize, delay: f32) -> Self {
+        TempEntity {
+            tick,
+            delay,
+            class_id: entity.server_class.id,
+            class_name: entity.server_class.name.to_owned(),
+            props: entity
+                .props
+                .iter()
+                .filter(|prop| !matches!(prop.value, PropertyValueEnum::None))
+                .map(|prop| (prop.entry.name.to_owned(), prop.value.to_owned()))
+                .collect(),
+        }
+    }
+}
+
+// Entries either name their class and start from blank props, or carry a delta against the
+// previous entry of the message.
+pub fn read_temp_entities(
+    state: &Arc<ParserState>,
+    bytes: &[u8],
+    tick: isize,
+) -> Result<Vec<TempEntity>, ParseError> {
+    let msg = CsvcMsgTempEntities::decode(bytes)
+        .map_err(|e| ParseError::decode("Failed to decode bytes into CsvcMsgTempEntities", e))?;
+
+    let server_classes = state.server_classes.lock();
+    let server_class_bits = *state.server_class_bits.lock() as usize;
+    let mut prop_indices_vec = state.prop_indices_vec.lock();
+
+    let mut r = BitReader::new_small_bit_reader(Cursor::new(msg.entity_data()));
+
+    let num_entries = msg.num_entries().max(0) as usize;
+    let mut temp_entities = Vec::with_capacity(num_entries);
+    let mut last: Option<Entity> = None;
+    for _ in 0..num_entries {
+        let delay = if r.read_bit() {
+            r.read_signed_int(TEMP_ENTITY_DELAY_BITS) as f32 / 100.0
+        } else {
+            0.0
+        };
+
+        let mut entity = if r.read_bit() {
+            let class_id = r.read_int(server_class_bits) as i32 - 1;
+            blank_temp_entity(&server_classes, class_id, tick)?
+        } else {
+            match last.take() {
+                Some(entity) => entity,
+                None => {
+                    return Err(ParseError::malformed(
+                        "Temp entity delta without a preceding entry",
+                    ))
+                }
+            }
+        };
+
+        entity.apply_update(state, &mut r, &mut prop_indices_vec)?;
+        temp_entities.push(TempEntity::from_entity(&entity, tick, delay));
+        last = Some(entity);
+    }
+
+    Ok(temp_entities)
+}
+
+fn blank_temp_entity(
+    server_classes: &[ServerClass],
+    class_id: i32,
+    tick: isize,
+) -> Result<Entity, ParseError> {
+    match server_classes.get(class_id as usize) {
+        Some(server_class) if class_id >= 0 => Ok(server_class.blank_entity(-1, tick)),
+        _ => Err(ParseError::malformed(format!(
+            "Unknown server class {class_id} for temp entity"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{sendtable::SendTableProperty, serverclass::FlattenedPropEntry};
+
+    // Writes bits the way `BitReader` reads them, least significant first.
+    #[derive(Default)]
+    struct Bits {
+        bytes: Vec<u8>,
+        len: usize,
+    }
+
+    impl Bits {
+        fn push(&mut self, value: u64, n: usize) -> &mut Self {
+            for i in 0..n {
+                if self.len.is_multiple_of(8) {
+                    self.bytes.push(0);
+                }
+                self.bytes[self.len / 8] |= (((value >> i) & 1) as u8) << (self.len % 8);
+                self.len += 1;
+            }
+            self
+        }
+
+        fn bit(&mut self, set: bool) -> &mut Self {
+            self.push(set as u64, 1)
+        }
+
+        // The index that ends a prop update.
+        fn end_of_props(&mut self) -> &mut Self {
+            self.bit(false).bit(false).push(127, 7).push(127, 7)
+        }
+    }
+
+    fn unsigned_prop(name: &str, num_bits: i32, index: i32) -> FlattenedPropEntry {
+        FlattenedPropEntry {
+            prop: SendTableProperty {
+                flags: 1,
+                name: name.to_owned(),
+                dt_name: "".to_owned(),
+                low_value: 0.0,
+                high_value: 0.0,
+                num_bits,
+                num_elems: 0,
+                priority: 0,
+                raw_type: 0,
+            },
+            array_elem_prop: None,
+            name: name.to_owned(),
+            index,
+        }
+    }
+
+    fn server_class(index: i32, name: &str, props: Vec<FlattenedPropEntry>) -> ServerClass {
+        ServerClass {
+            id: index,
+            name: name.to_owned(),
+            dt_id: -1,
+            dt_name: format!("DT_{}", &name[1..]),
+            base_classes_by_name: None,
+            flattened_props: Some(props),
+            prop_name_to_idx: None,
+            instance_baseline: None,
+            preprocessed_baseline: None,
+            created_handlers: None,
+            index,
+        }
+    }
+
+    fn integer(temp_entity: &TempEntity, name: &str) -> Option<i32> {
+        match temp_entity.prop(name) {
+            Some(PropertyValueEnum::Integer(value)) => Some(**value),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn read_temp_entities_decodes_new_and_delta_entries() {
+        let state = Arc::new(ParserState::default());
+        *state.server_classes.lock() = vec![
+            server_class(0, "CTEDust", Vec::new()),
+            server_class(
+                1,
+                "CTEBloodSprite",
+                vec![
+                    unsigned_prop("m_nSize", 8, 0),
+                    unsigned_prop("m_nColor", 4, 1),
+                ],
+            ),
+        ];
+        *state.server_class_bits.lock() = 2;
+
+        let mut bits = Bits::default();
+        // A CTEBloodSprite, half a second late, with both props.
+        bits.bit(true).push(50, TEMP_ENTITY_DELAY_BITS);
+        bits.bit(true).push(2, 2);
+        bits.bit(true).bit(true).bit(true).end_of_props();
+        bits.push(12, 8).push(5, 4);
+        // A delta against it that only changes the color.
+        bits.bit(false).bit(false);
+        bits.bit(true)
+            .bit(false)
+            .bit(true)
+            .push(1, 3)
+            .end_of_props();
+        bits.push(9, 4);
+
+        let msg = CsvcMsgTempEntities {
+            reliable: None,
+            num_entries: Some(2),
+            entity_data: Some(bits.bytes.clone()),
+        };
+        let temp_entities = read_temp_entities(&state, &msg.encode_to_vec(), 640).unwrap();
+
+        assert_eq!(temp_entities.len(), 2);
+        assert_eq!(temp_entities[0].tick, 640);
+        assert_eq!(temp_entities[0].delay, 0.5);
+        assert_eq!(temp_entities[0].class_name, "CTEBloodSprite");
+        assert_eq!(integer(&temp_entities[0], "m_nSize"), Some(12));
+        assert_eq!(integer(&temp_entities[0], "m_nColor"), Some(5));
+
+        assert_eq!(temp_entities[1].delay, 0.0);
+        assert_eq!(temp_entities[1].class_id, 1);
+        assert_eq!(integer(&temp_entities[1], "m_nSize"), Some(12));
+        assert_eq!(integer(&temp_entities[1], "m_nColor"), Some(9));
+        assert!(temp_entities[1].origin().is_none());
+    }
+
+    #[test]
+    fn read_temp_entities_rejects_a_leading_delta() {
+        let state = Arc::new(ParserState::default());
+        let msg = CsvcMsgTempEntities {
+            reliable: None,
+            num_entries: Some(1),
+            entity_data: Some(vec![0; 4]),
+        };
+
+        let err = read_temp_entities(&state, &msg.encode_to_vec(), 0).unwrap_err();
+        assert!(matches!(err, ParseError::Malformed { .. }));
+    }
+}
@@ -14,3 +14,95 @@ pub struct CnetMsgTick {
     #[prost(uint32, optional, tag = "7")]
     pub hltv_replay_flags: Option<u32>,
 }
+
+// Server timing of one network tick, as emitted on the "server_timing" channel. Times are in
+// microseconds, as sent by the server.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerTiming {
+    // Demo tick of the frame the message came in.
+    pub tick: isize,
+    pub server_tick: u32,
+    pub host_computation_time: u32,
+    pub host_computation_time_std_deviation: u32,
+    pub host_frame_start_time_std_deviation: u32,
+    pub hltv_replay_flags: u32,
+}
+
+impl ServerTiming {
+    pub fn new(tick: isize, msg: &CnetMsgTick) -> Self {
+        ServerTiming {
+            tick,
+            server_tick: msg.tick,
+            host_computation_time: msg.host_computationtime(),
+            host_computation_time_std_deviation: msg.host_computationtime_std_deviation(),
+            host_frame_start_time_std_deviation: msg.host_framestarttime_std_deviation(),
+            hltv_replay_flags: msg.hltv_replay_flags(),
+        }
+    }
+
+    // GOTV is showing a replay (e.g. a killcam) rather than the live game.
+    pub fn is_replay(&self) -> bool {
+        self.hltv_replay_flags != 0
+    }
+}
+
+// Aggregated over every CNETMsg_Tick of the demo so far.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServerTimingStats {
+    pub samples: u64,
+    pub replay_samples: u64,
+    pub first_server_tick: Option<u32>,
+    pub last_server_tick: Option<u32>,
+    pub max_host_computation_time: u32,
+    pub max_host_computation_time_std_deviation: u32,
+    pub max_host_frame_start_time_std_deviation: u32,
+    total_host_computation_time: u64,
+    total_host_computation_time_std_deviation: u64,
+    total_host_frame_start_time_std_deviation: u64,
+}
+
+impl ServerTimingStats {
+    pub fn add(&mut self, timing: &ServerTiming) {
+        self.samples += 1;
+        if timing.is_replay() {
+            self.replay_samples += 1;
+        }
+        self.first_server_tick.get_or_insert(timing.server_tick);
+        self.last_server_tick = Some(timing.server_tick);
+
+        self.max_host_computation_time = self
+            .max_host_computation_time
+            .max(timing.host_computation_time);
+        self.max_host_computation_time_std_deviation = self
+            .max_host_computation_time_std_deviation
+            .max(timing.host_computation_time_std_deviation);
+        self.max_host_frame_start_time_std_deviation = self
+            .max_host_frame_start_time_std_deviation
+            .max(timing.host_frame_start_time_std_deviation);
+
+        self.total_host_computation_time += timing.host_computation_time as u64;
+        self.total_host_computation_time_std_deviation +=
+            timing.host_computation_time_std_deviation as u64;
+        self.total_host_frame_start_time_std_deviation +=
+            timing.host_frame_start_time_std_deviation as u64;
+    }
+
+    pub fn mean_host_computation_time(&self) -> f64 {
+        self.mean(self.total_host_computation_time)
+    }
+
+    pub fn mean_host_computation_time_std_deviation(&self) -> f64 {
+        self.mean(self.total_host_computation_time_std_deviation)
+    }
+
+    pub fn mean_host_frame_start_time_std_deviation(&self) -> f64 {
+        self.mean(self.total_host_frame_start_time_std_deviation)
+    }
+
+    fn mean(&self, total: u64) -> f64 {
+        if self.samples == 0 {
+            return 0.0;
+        }
+        total as f64 / self.samples as f64
+    }
+}
@@ -0,0 +1,196 @@
+use std::io::Cursor;
+
+use custom_bitreader::BitReader;
+use serde::{Deserialize, Serialize};
+
+const MAX_EDICT_BITS: usize = 11;
+const WEAPON_SUBTYPE_BITS: usize = 6;
+const IMPULSE_BITS: usize = 8;
+const MOUSE_DELTA_BITS: usize = 16;
+
+pub const IN_ATTACK: u32 = 1 << 0;
+pub const IN_JUMP: u32 = 1 << 1;
+pub const IN_DUCK: u32 = 1 << 2;
+pub const IN_FORWARD: u32 = 1 << 3;
+pub const IN_BACK: u32 = 1 << 4;
+pub const IN_USE: u32 = 1 << 5;
+pub const IN_MOVELEFT: u32 = 1 << 9;
+pub const IN_MOVERIGHT: u32 = 1 << 10;
+pub const IN_ATTACK2: u32 = 1 << 11;
+pub const IN_RELOAD: u32 = 1 << 13;
+pub const IN_SCORE: u32 = 1 << 16;
+pub const IN_SPEED: u32 = 1 << 17;
+pub const IN_ZOOM: u32 = 1 << 19;
+pub const IN_LOOKSPIN: u32 = 1 << 25;
+
+// The input of the recording player for one tick, from a dem_usercmd frame. Only POV demos
+// have these.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UserCmd {
+    pub tick: isize,
+    pub outgoing_sequence: i32,
+
+    pub command_number: u32,
+    pub tick_count: u32,
+    // Pitch, yaw, roll.
+    pub view_angles: [f32; 3],
+    pub aim_direction: [f32; 3],
+    pub forward_move: f32,
+    pub side_move: f32,
+    pub up_move: f32,
+    // IN_* flags.
+    pub buttons: u32,
+    pub impulse: u8,
+    pub weapon_select: u32,
+    pub weapon_subtype: u32,
+    pub mouse_dx: i16,
+    pub mouse_dy: i16,
+}
+
+impl UserCmd {
+    // Demos write every command as a delta against an empty one, so fields that were not sent
+    // keep their zero value; command_number and tick_count count up from it.
+    pub fn decode(tick: isize, outgoing_sequence: i32, data: &[u8]) -> Self {
+        let mut r = BitReader::new_small_bit_reader(Cursor::new(data));
+        let mut cmd = UserCmd {
+            tick,
+            outgoing_sequence,
+            command_number: 1,
+            tick_count: 1,
+            ..Default::default()
+        };
+
+        if r.read_bit() {
+            cmd.command_number = r.read_int(32) as u32;
+        }
+        if r.read_bit() {
+            cmd.tick_count = r.read_int(32) as u32;
+        }
+
+        for angle in cmd.view_angles.iter_mut() {
+            if r.read_bit() {
+                *angle = r.read_float();
+            }
+        }
+        for direction in cmd.aim_direction.iter_mut() {
+            if r.read_bit() {
+                *direction = r.read_float();
+            }
+        }
+
+        if r.read_bit() {
+            cmd.forward_move = r.read_float();
+        }
+        if r.read_bit() {
+            cmd.side_move = r.read_float();
+        }
+        if r.read_bit() {
+            cmd.up_move = r.read_float();
+        }
+
+        if r.read_bit() {
+            cmd.buttons = r.read_int(32) as u32;
+        }
+        if r.read_bit() {
+            cmd.impulse = r.read_int(IMPULSE_BITS) as u8;
+        }
+        if r.read_bit() {
+            cmd.weapon_select = r.read_int(MAX_EDICT_BITS) as u32;
+            if r.read_bit() {
+                cmd.weapon_subtype = r.read_int(WEAPON_SUBTYPE_BITS) as u32;
+            }
+        }
+
+        if r.read_bit() {
+            cmd.mouse_dx = r.read_signed_int(MOUSE_DELTA_BITS) as i16;
+        }
+        if r.read_bit() {
+            cmd.mouse_dy = r.read_signed_int(MOUSE_DELTA_BITS) as i16;
+        }
+
+        cmd
+    }
+
+    #[inline]
+    pub fn pressed(&self, button: u32) -> bool {
+        self.buttons & button != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Writes bits the way `BitReader` reads them, least significant first.
+    #[derive(Default)]
+    struct Bits {
+        bytes: Vec<u8>,
+        len: usize,
+    }
+
+    impl Bits {
+        fn push(&mut self, value: u64, n: usize) -> &mut Self {
+            for i in 0..n {
+                if self.len.is_multiple_of(8) {
+                    self.bytes.push(0);
+                }
+                self.bytes[self.len / 8] |= (((value >> i) & 1) as u8) << (self.len % 8);
+                self.len += 1;
+            }
+            self
+        }
+
+        fn bit(&mut self, set: bool) -> &mut Self {
+            self.push(set as u64, 1)
+        }
+    }
+
+    #[test]
+    fn decode_reads_sent_fields() {
+        let mut bits = Bits::default();
+        bits.bit(true).push(1234, 32); // command_number
+        bits.bit(false); // tick_count
+        bits.bit(false)
+            .bit(true)
+            .push(90.5_f32.to_bits() as u64, 32)
+            .bit(false); // view_angles
+        bits.bit(false).bit(false).bit(false); // aim_direction
+        bits.bit(true).push(450.0_f32.to_bits() as u64, 32); // forward_move
+        bits.bit(false).bit(false); // side_move, up_move
+        bits.bit(true).push((IN_ATTACK | IN_DUCK) as u64, 32); // buttons
+        bits.bit(false); // impulse
+        bits.bit(true)
+            .push(42, MAX_EDICT_BITS)
+            .bit(true)
+            .push(3, WEAPON_SUBTYPE_BITS);
+        bits.bit(true).push(-5_i16 as u16 as u64, MOUSE_DELTA_BITS); // mouse_dx
+        bits.bit(false); // mouse_dy
+
+        let cmd = UserCmd::decode(100, 7, &bits.bytes);
+        assert_eq!(cmd.tick, 100);
+        assert_eq!(cmd.outgoing_sequence, 7);
+        assert_eq!(cmd.command_number, 1234);
+        assert_eq!(cmd.tick_count, 1);
+        assert_eq!(cmd.view_angles, [0.0, 90.5, 0.0]);
+        assert_eq!(cmd.forward_move, 450.0);
+        assert_eq!(cmd.side_move, 0.0);
+        assert!(cmd.pressed(IN_ATTACK) && cmd.pressed(IN_DUCK) && !cmd.pressed(IN_JUMP));
+        assert_eq!((cmd.weapon_select, cmd.weapon_subtype), (42, 3));
+        assert_eq!((cmd.mouse_dx, cmd.mouse_dy), (-5, 0));
+    }
+
+    #[test]
+    fn decode_of_nothing_sent_is_the_empty_command() {
+        let cmd = UserCmd::decode(5, 1, &[0; 4]);
+        assert_eq!(
+            cmd,
+            UserCmd {
+                tick: 5,
+                outgoing_sequence: 1,
+                command_number: 1,
+                tick_count: 1,
+                ..Default::default()
+            }
+        );
+    }
+}
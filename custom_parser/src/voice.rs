@@ -0,0 +1,743 @@
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use ahash::AHashMap as HashMap;
+use prost::Message;
+use serde::{Deserialize, Serialize};
+
+use crate::ParseError;
+
+const VOICE_DATA_FORMAT_STEAM: i32 = 0;
+
+const STEAM_VOICE_SILENCE: u8 = 0x00;
+const STEAM_VOICE_OPUS_PLC: u8 = 0x06;
+const STEAM_VOICE_UNKNOWN: u8 = 0x0A;
+const STEAM_VOICE_SAMPLE_RATE: u8 = 0x0B;
+const STEAM_VOICE_STEAM_ID_LEN: usize = 8;
+const STEAM_VOICE_CRC_LEN: usize = 4;
+const STEAM_VOICE_DEFAULT_SAMPLE_RATE: u32 = 24000;
+
+const ENGINE_CELT_SAMPLE_RATE: u32 = 22050;
+const ENGINE_SPEEX_SAMPLE_RATE: u32 = 8000;
+
+const OPUS_SAMPLE_RATE: u64 = 48000;
+// TOC byte of a CELT-only 20 ms mono packet. On its own, without a frame, decoders play it as
+// 20 ms of nothing.
+const OPUS_EMPTY_PACKET: [u8; 1] = [0xF8];
+const OPUS_EMPTY_PACKET_SAMPLES: u64 = 960;
+const OPUS_VENDOR: &str = "rust-demofile";
+
+const OGG_HEADER_BOS: u8 = 0x02;
+const OGG_HEADER_EOS: u8 = 0x04;
+
+#[derive(Clone, PartialEq, Eq, Message)]
+pub struct CsvcMsgVoiceInit {
+    #[prost(int32, optional, tag = "1")]
+    pub quality: Option<i32>,
+    #[prost(string, optional, tag = "2")]
+    pub codec: Option<String>,
+    #[prost(int32, optional, tag = "3")]
+    pub version: Option<i32>,
+}
+
+#[derive(Clone, PartialEq, Eq, Message)]
+pub struct CsvcMsgVoiceData {
+    #[prost(int32, optional, tag = "1")]
+    pub client: Option<i32>,
+    #[prost(bool, optional, tag = "2")]
+    pub proximity: Option<bool>,
+    #[prost(fixed64, optional, tag = "3")]
+    pub xuid: Option<u64>,
+    #[prost(int32, optional, tag = "4")]
+    pub audible_mask: Option<i32>,
+    #[prost(bytes = "vec", optional, tag = "5")]
+    pub voice_data: Option<Vec<u8>>,
+    #[prost(bool, optional, tag = "6")]
+    pub caster: Option<bool>,
+    #[prost(int32, optional, tag = "7", default = "1")]
+    pub format: Option<i32>,
+    #[prost(int32, optional, tag = "8")]
+    pub sequence_bytes: Option<i32>,
+    #[prost(uint32, optional, tag = "9")]
+    pub section_number: Option<u32>,
+    #[prost(uint32, optional, tag = "10")]
+    pub uncompressed_sample_offset: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VoiceFormat {
+    // Steam voice packets, holding Opus frames.
+    Steam,
+    // Frames of the codec announced by CSVCMsg_VoiceInit, e.g. vaudio_celt.
+    Engine,
+}
+
+// Who a voice track belongs to. Packets that name no SteamID can only be told apart by the
+// client index they came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Speaker {
+    SteamId(u64),
+    Client(i32),
+}
+
+impl fmt::Display for Speaker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Speaker::SteamId(steam_id) => write!(f, "{steam_id}"),
+            Speaker::Client(client) => write!(f, "client_{client}"),
+        }
+    }
+}
+
+// One CSVCMsg_VoiceData, as emitted on the "voice_data" channel.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoicePacket {
+    pub tick: isize,
+    // Seconds since the start of the demo.
+    pub time: f64,
+    pub client: i32,
+    // None when the packet names nobody.
+    pub steam_id: Option<u64>,
+    pub format: VoiceFormat,
+    pub section_number: u32,
+    pub sequence_bytes: i32,
+    pub uncompressed_sample_offset: u32,
+    pub data: Vec<u8>,
+}
+
+impl VoicePacket {
+    pub fn new(tick: isize, time: f64, msg: CsvcMsgVoiceData) -> Self {
+        let format = if msg.format() == VOICE_DATA_FORMAT_STEAM {
+            VoiceFormat::Steam
+        } else {
+            VoiceFormat::Engine
+        };
+
+        let mut steam_id = msg.xuid();
+        if steam_id == 0 && format == VoiceFormat::Steam {
+            if let Some(id) = msg.voice_data().get(..STEAM_VOICE_STEAM_ID_LEN) {
+                steam_id = u64::from_le_bytes(id.try_into().unwrap());
+            }
+        }
+
+        VoicePacket {
+            tick,
+            time,
+            client: msg.client(),
+            steam_id: (steam_id != 0).then_some(steam_id),
+            format,
+            section_number: msg.section_number(),
+            sequence_bytes: msg.sequence_bytes(),
+            uncompressed_sample_offset: msg.uncompressed_sample_offset(),
+            data: msg.voice_data.unwrap_or_default(),
+        }
+    }
+
+    pub fn speaker(&self) -> Speaker {
+        match self.steam_id {
+            Some(steam_id) => Speaker::SteamId(steam_id),
+            None => Speaker::Client(self.client),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VoiceFrame {
+    Opus(Vec<u8>),
+    // Samples of silence, at the rate of the segment.
+    Silence(u16),
+    // The sender restarted its encoder.
+    Reset,
+    // A frame of the engine codec, handed to the `VoiceDecoder` as is.
+    Engine(Vec<u8>),
+}
+
+// The frames of one voice packet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoiceSegment {
+    pub tick: isize,
+    pub time: f64,
+    pub sample_rate: u32,
+    pub frames: Vec<VoiceFrame>,
+}
+
+// Everything one speaker said, in demo order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoiceTrack {
+    pub speaker: Speaker,
+    pub format: VoiceFormat,
+    // "steam" for Steam voice, the CSVCMsg_VoiceInit codec otherwise.
+    pub codec: String,
+    pub segments: Vec<VoiceSegment>,
+}
+
+// Turns frames into mono 16 bit PCM at `sample_rate`, e.g. by wrapping an Opus or CELT
+// decoder. Needed for WAV export only.
+pub trait VoiceDecoder {
+    fn decode(
+        &mut self,
+        frame: &VoiceFrame,
+        sample_rate: u32,
+        out: &mut Vec<i16>,
+    ) -> io::Result<()>;
+}
+
+// Collects voice packets per speaker, see `Parser::record_voice`. No decoder is shipped:
+// Steam voice can be remuxed to Opus as is, but WAV export, and any export of Engine
+// (e.g. CELT) tracks, needs a `VoiceDecoder` from the caller.
+#[derive(Debug, Clone, Default)]
+pub struct VoiceRecorder {
+    pub codec: Option<String>,
+    pub tracks: HashMap<Speaker, VoiceTrack>,
+    // Packets the parser left out because they could not be read.
+    pub skipped_packets: usize,
+}
+
+impl VoiceRecorder {
+    pub fn add(&mut self, packet: &VoicePacket) -> Result<(), ParseError> {
+        let (codec, segment) = match packet.format {
+            VoiceFormat::Steam => ("steam".to_owned(), read_steam_voice(packet)?),
+            VoiceFormat::Engine => {
+                let codec = self.codec.to_owned().unwrap_or_default();
+                let sample_rate = if codec.contains("speex") {
+                    ENGINE_SPEEX_SAMPLE_RATE
+                } else {
+                    ENGINE_CELT_SAMPLE_RATE
+                };
+                let segment = VoiceSegment {
+                    tick: packet.tick,
+                    time: packet.time,
+                    sample_rate,
+                    frames: vec![VoiceFrame::Engine(packet.data.to_owned())],
+                };
+                (codec, segment)
+            }
+        };
+
+        self.tracks
+            .entry(packet.speaker())
+            .or_insert_with(|| VoiceTrack {
+                speaker: packet.speaker(),
+                format: packet.format,
+                codec,
+                segments: Vec::new(),
+            })
+            .segments
+            .push(segment);
+
+        Ok(())
+    }
+
+    pub fn track(&self, speaker: Speaker) -> Option<&VoiceTrack> {
+        self.tracks.get(&speaker)
+    }
+
+    // Writes <steam_id>.opus (client_<index>.opus for unnamed speakers) for every Steam voice
+    // track into `dir`.
+    pub fn export_opus(&self, dir: &Path) -> io::Result<()> {
+        for track in self.tracks.values() {
+            if track.format == VoiceFormat::Steam {
+                track.save_ogg_opus(dir.join(format!("{}.opus", track.speaker)))?;
+            }
+        }
+        Ok(())
+    }
+
+    // Writes <steam_id>.wav (client_<index>.wav for unnamed speakers) for every track into
+    // `dir`.
+    pub fn export_wav<D: VoiceDecoder>(&self, dir: &Path, decoder: &mut D) -> io::Result<()> {
+        for track in self.tracks.values() {
+            track.save_wav(dir.join(format!("{}.wav", track.speaker)), decoder)?;
+        }
+        Ok(())
+    }
+}
+
+impl VoiceTrack {
+    pub fn sample_rate(&self) -> u32 {
+        self.segments
+            .first()
+            .map_or(STEAM_VOICE_DEFAULT_SAMPLE_RATE, |segment| {
+                segment.sample_rate
+            })
+    }
+
+    pub fn save_ogg_opus<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut w = BufWriter::new(File::create(path)?);
+        self.write_ogg_opus(&mut w)?;
+        w.flush()
+    }
+
+    pub fn save_wav<P: AsRef<Path>, D: VoiceDecoder>(
+        &self,
+        path: P,
+        decoder: &mut D,
+    ) -> io::Result<()> {
+        let mut w = BufWriter::new(File::create(path)?);
+        self.write_wav(&mut w, decoder)?;
+        w.flush()
+    }
+
+    // Remuxes the Opus frames without decoding them. Pauses between packets are kept, so the
+    // file lines up with the demo from the first time the player spoke.
+    pub fn write_ogg_opus<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        if self.format != VoiceFormat::Steam {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("{} voice can't be written as Opus", self.codec),
+            ));
+        }
+
+        let mut packets: Vec<(&[u8], u64)> = Vec::new();
+        let mut written = 0_u64;
+        let start = self.segments.first().map_or(0.0, |segment| segment.time);
+        for segment in &self.segments {
+            let due = ((segment.time - start) * OPUS_SAMPLE_RATE as f64) as u64;
+            while written + OPUS_EMPTY_PACKET_SAMPLES <= due {
+                written += OPUS_EMPTY_PACKET_SAMPLES;
+                packets.push((&OPUS_EMPTY_PACKET, written));
+            }
+
+            for frame in &segment.frames {
+                match frame {
+                    VoiceFrame::Opus(packet) => {
+                        written += opus_packet_samples(packet);
+                        packets.push((packet, written));
+                    }
+                    VoiceFrame::Silence(samples) => {
+                        let silence =
+                            *samples as u64 * OPUS_SAMPLE_RATE / segment.sample_rate.max(1) as u64;
+                        for _ in 0..silence.div_ceil(OPUS_EMPTY_PACKET_SAMPLES) {
+                            written += OPUS_EMPTY_PACKET_SAMPLES;
+                            packets.push((&OPUS_EMPTY_PACKET, written));
+                        }
+                    }
+                    VoiceFrame::Reset => {}
+                    VoiceFrame::Engine(_) => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::Unsupported,
+                            format!(
+                                "Voice of {} holds {} frames, which can't be written as Opus",
+                                self.speaker, self.codec
+                            ),
+                        ))
+                    }
+                }
+            }
+        }
+
+        let mut head = b"OpusHead".to_vec();
+        head.push(1);
+        head.push(1);
+        head.extend(0_u16.to_le_bytes());
+        head.extend(self.sample_rate().to_le_bytes());
+        head.extend(0_i16.to_le_bytes());
+        head.push(0);
+
+        let mut tags = b"OpusTags".to_vec();
+        tags.extend((OPUS_VENDOR.len() as u32).to_le_bytes());
+        tags.extend(OPUS_VENDOR.as_bytes());
+        tags.extend(0_u32.to_le_bytes());
+
+        let serial = match self.speaker {
+            Speaker::SteamId(steam_id) => steam_id as u32,
+            Speaker::Client(client) => client as u32,
+        };
+        write_ogg_page(w, serial, 0, OGG_HEADER_BOS, 0, &head)?;
+        let flags = if packets.is_empty() {
+            OGG_HEADER_EOS
+        } else {
+            0
+        };
+        write_ogg_page(w, serial, 1, flags, 0, &tags)?;
+
+        let last = packets.len().saturating_sub(1);
+        for (i, (packet, granule)) in packets.into_iter().enumerate() {
+            let flags = if i == last { OGG_HEADER_EOS } else { 0 };
+            write_ogg_page(w, serial, i as u32 + 2, flags, granule, packet)?;
+        }
+
+        Ok(())
+    }
+
+    // Mono 16 bit PCM at the rate of the first segment, with silence filling the pauses
+    // between packets. Segments at another rate are resampled to it.
+    pub fn write_wav<W: Write, D: VoiceDecoder>(
+        &self,
+        w: &mut W,
+        decoder: &mut D,
+    ) -> io::Result<()> {
+        let sample_rate = self.sample_rate();
+        let mut samples: Vec<i16> = Vec::new();
+        let mut decoded: Vec<i16> = Vec::new();
+        let start = self.segments.first().map_or(0.0, |segment| segment.time);
+        for segment in &self.segments {
+            let due = ((segment.time - start) * sample_rate as f64) as usize;
+            if samples.len() < due {
+                samples.resize(due, 0);
+            }
+
+            decoded.clear();
+            for frame in &segment.frames {
+                match frame {
+                    VoiceFrame::Silence(n) => decoded.resize(decoded.len() + *n as usize, 0),
+                    frame => decoder.decode(frame, segment.sample_rate, &mut decoded)?,
+                }
+            }
+            resample(&decoded, segment.sample_rate, sample_rate, &mut samples);
+        }
+
+        let data_len = (samples.len() * 2) as u32;
+        w.write_all(b"RIFF")?;
+        w.write_all(&(36 + data_len).to_le_bytes())?;
+        w.write_all(b"WAVEfmt ")?;
+        w.write_all(&16_u32.to_le_bytes())?;
+        w.write_all(&1_u16.to_le_bytes())?;
+        w.write_all(&1_u16.to_le_bytes())?;
+        w.write_all(&sample_rate.to_le_bytes())?;
+        w.write_all(&(sample_rate * 2).to_le_bytes())?;
+        w.write_all(&2_u16.to_le_bytes())?;
+        w.write_all(&16_u16.to_le_bytes())?;
+        w.write_all(b"data")?;
+        w.write_all(&data_len.to_le_bytes())?;
+        for sample in samples {
+            w.write_all(&sample.to_le_bytes())?;
+        }
+
+        Ok(())
+    }
+}
+
+// Linear interpolation from `from` Hz to `to` Hz, appended to `out`.
+fn resample(samples: &[i16], from: u32, to: u32, out: &mut Vec<i16>) {
+    if from == to || from == 0 || samples.is_empty() {
+        out.extend_from_slice(samples);
+        return;
+    }
+
+    let len = (samples.len() as u64 * to as u64 / from as u64) as usize;
+    let step = from as f64 / to as f64;
+    out.extend((0..len).map(|i| {
+        let pos = i as f64 * step;
+        let j = pos as usize;
+        let a = samples[j.min(samples.len() - 1)] as f64;
+        let b = samples[(j + 1).min(samples.len() - 1)] as f64;
+        (a + (b - a) * pos.fract()).round() as i16
+    }));
+}
+
+// Steam voice: the sender's SteamID, then typed payloads, then a CRC32 of everything before it.
+fn read_steam_voice(packet: &VoicePacket) -> Result<VoiceSegment, ParseError> {
+    let data = &packet.data;
+    let malformed = || {
+        ParseError::malformed(format!(
+            "Malformed Steam voice packet at tick {}",
+            packet.tick
+        ))
+    };
+    if data.len() < STEAM_VOICE_STEAM_ID_LEN + STEAM_VOICE_CRC_LEN {
+        return Err(malformed());
+    }
+
+    let end = data.len() - STEAM_VOICE_CRC_LEN;
+    let crc = u32::from_le_bytes(data[end..].try_into().unwrap());
+    if crc32(&data[..end]) != crc {
+        return Err(ParseError::malformed(format!(
+            "Steam voice packet at tick {} fails its CRC check",
+            packet.tick
+        )));
+    }
+
+    let mut segment = VoiceSegment {
+        tick: packet.tick,
+        time: packet.time,
+        sample_rate: STEAM_VOICE_DEFAULT_SAMPLE_RATE,
+        frames: Vec::new(),
+    };
+
+    let mut pos = STEAM_VOICE_STEAM_ID_LEN;
+    // Reads never go past `limit`, so a bad length can't run into the CRC.
+    let read = |pos: &mut usize, len: usize, limit: usize| -> Result<&[u8], ParseError> {
+        if *pos + len > limit {
+            return Err(malformed());
+        }
+        *pos += len;
+        Ok(&data[*pos - len..*pos])
+    };
+    let read_u16 = |pos: &mut usize, limit: usize| -> Result<u16, ParseError> {
+        let bytes = read(pos, 2, limit)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    };
+
+    while pos < end {
+        let payload_type = data[pos];
+        pos += 1;
+
+        match payload_type {
+            STEAM_VOICE_SAMPLE_RATE => segment.sample_rate = read_u16(&mut pos, end)? as u32,
+            STEAM_VOICE_UNKNOWN => {
+                read_u16(&mut pos, end)?;
+            }
+            STEAM_VOICE_SILENCE => {
+                let samples = read_u16(&mut pos, end)?;
+                segment.frames.push(VoiceFrame::Silence(samples));
+            }
+            STEAM_VOICE_OPUS_PLC => {
+                let len = read_u16(&mut pos, end)? as usize;
+                let payload_end = pos + len;
+                if payload_end > end {
+                    return Err(malformed());
+                }
+
+                while pos < payload_end {
+                    let frame_len = read_u16(&mut pos, payload_end)?;
+                    if frame_len == u16::MAX {
+                        segment.frames.push(VoiceFrame::Reset);
+                        continue;
+                    }
+                    // Sequence number of the frame.
+                    read_u16(&mut pos, payload_end)?;
+
+                    let frame = read(&mut pos, frame_len as usize, payload_end)?;
+                    segment.frames.push(VoiceFrame::Opus(frame.to_vec()));
+                }
+            }
+            // Without its length the rest can't be read, but the frames before it are good.
+            _ => break,
+        }
+    }
+
+    Ok(segment)
+}
+
+// Duration of an Opus packet in 48 kHz samples, from its TOC byte (RFC 6716, 3.1).
+fn opus_packet_samples(packet: &[u8]) -> u64 {
+    let Some(&toc) = packet.first() else {
+        return 0;
+    };
+
+    let config = (toc >> 3) as usize;
+    let frame_samples = match config {
+        0..=11 => [480, 960, 1920, 2880][config % 4],
+        12..=15 => [480, 960][config % 2],
+        _ => [120, 240, 480, 960][config % 4],
+    };
+    let frames = match toc & 3 {
+        0 => 1,
+        1 | 2 => 2,
+        _ => packet.get(1).map_or(0, |count| (count & 0x3F) as u64),
+    };
+
+    frame_samples * frames
+}
+
+fn write_ogg_page<W: Write>(
+    w: &mut W,
+    serial: u32,
+    sequence: u32,
+    flags: u8,
+    granule: u64,
+    packet: &[u8],
+) -> io::Result<()> {
+    let mut page = b"OggS".to_vec();
+    page.push(0);
+    page.push(flags);
+    page.extend(granule.to_le_bytes());
+    page.extend(serial.to_le_bytes());
+    page.extend(sequence.to_le_bytes());
+    page.extend(0_u32.to_le_bytes());
+
+    let mut lacing = vec![255_u8; packet.len() / 255];
+    lacing.push((packet.len() % 255) as u8);
+    if lacing.len() > 255 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Opus packet too large for a single Ogg page",
+        ));
+    }
+    page.push(lacing.len() as u8);
+    page.extend(lacing);
+    page.extend(packet);
+
+    let crc = ogg_crc(&page);
+    page[22..26].copy_from_slice(&crc.to_le_bytes());
+
+    w.write_all(&page)
+}
+
+fn ogg_crc(bytes: &[u8]) -> u32 {
+    let mut crc = 0_u32;
+    for &byte in bytes {
+        crc ^= (byte as u32) << 24;
+        for _ in 0..8 {
+            crc = if crc & 0x8000_0000 != 0 {
+                (crc << 1) ^ 0x04C1_1DB7
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+// CRC-32 as used by zlib, reflected with polynomial 0xEDB88320.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0_u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ogg_crc_matches_the_reference() {
+        assert_eq!(ogg_crc(b""), 0);
+        // CRC-32/CKSUM of the standard check input, without its final inversion.
+        assert_eq!(ogg_crc(b"123456789"), !0x765E_7680);
+    }
+
+    // A decoder that turns every frame into the same short ramp.
+    struct Ramp;
+
+    impl VoiceDecoder for Ramp {
+        fn decode(&mut self, _: &VoiceFrame, _: u32, out: &mut Vec<i16>) -> io::Result<()> {
+            out.extend([0, 100, 200, 300]);
+            Ok(())
+        }
+    }
+
+    fn steam_packet(payloads: &[u8]) -> VoicePacket {
+        let mut data = 76561198000000000_u64.to_le_bytes().to_vec();
+        data.extend(payloads);
+        data.extend(crc32(&data).to_le_bytes());
+        VoicePacket {
+            tick: 64,
+            time: 1.0,
+            client: 1,
+            steam_id: Some(76561198000000000),
+            format: VoiceFormat::Steam,
+            section_number: 0,
+            sequence_bytes: 0,
+            uncompressed_sample_offset: 0,
+            data,
+        }
+    }
+
+    fn track(format: VoiceFormat, segments: Vec<(u32, Vec<VoiceFrame>)>) -> VoiceTrack {
+        VoiceTrack {
+            speaker: Speaker::Client(1),
+            format,
+            codec: "steam".to_owned(),
+            segments: segments
+                .into_iter()
+                .map(|(sample_rate, frames)| VoiceSegment {
+                    tick: 64,
+                    time: 1.0,
+                    sample_rate,
+                    frames,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn crc32_matches_the_reference() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn steam_voice_stops_at_an_unknown_payload() {
+        let mut payloads = vec![STEAM_VOICE_SAMPLE_RATE];
+        payloads.extend(16000_u16.to_le_bytes());
+        payloads.push(STEAM_VOICE_SILENCE);
+        payloads.extend(16_u16.to_le_bytes());
+        payloads.extend([0x42, 0x01, 0x02, 0x03]);
+        let packet = steam_packet(&payloads);
+
+        let segment = read_steam_voice(&packet).unwrap();
+
+        assert_eq!(segment.sample_rate, 16000);
+        assert_eq!(segment.frames, [VoiceFrame::Silence(16)]);
+    }
+
+    #[test]
+    fn steam_voice_checks_the_crc() {
+        let mut packet = steam_packet(&[STEAM_VOICE_SILENCE, 0x10, 0x00]);
+        *packet.data.last_mut().unwrap() ^= 1;
+
+        assert!(matches!(
+            read_steam_voice(&packet),
+            Err(ParseError::Malformed { .. })
+        ));
+    }
+
+    #[test]
+    fn opus_export_rejects_engine_frames() {
+        let track = track(
+            VoiceFormat::Steam,
+            vec![(24000, vec![VoiceFrame::Engine(vec![1, 2, 3])])],
+        );
+
+        let err = track.write_ogg_opus(&mut Vec::new()).unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn wav_export_resamples_to_the_track_rate() {
+        let track = track(
+            VoiceFormat::Steam,
+            vec![
+                (16000, vec![VoiceFrame::Opus(vec![0xF8])]),
+                (8000, vec![VoiceFrame::Opus(vec![0xF8])]),
+            ],
+        );
+
+        let mut wav = Vec::new();
+        track.write_wav(&mut wav, &mut Ramp).unwrap();
+
+        assert_eq!(&wav[24..28], &16000_u32.to_le_bytes());
+        let samples: Vec<i16> = wav[44..]
+            .chunks(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]))
+            .collect();
+        assert_eq!(
+            samples,
+            [0, 100, 200, 300, 0, 50, 100, 150, 200, 250, 300, 300]
+        );
+    }
+
+    #[test]
+    fn opus_packet_samples_follow_the_toc_byte() {
+        assert_eq!(opus_packet_samples(&[]), 0);
+        assert_eq!(
+            opus_packet_samples(&OPUS_EMPTY_PACKET),
+            OPUS_EMPTY_PACKET_SAMPLES
+        );
+        // SILK 10 ms, one frame.
+        assert_eq!(opus_packet_samples(&[0x00]), 480);
+        // SILK 20 ms, two frames.
+        assert_eq!(opus_packet_samples(&[0x09]), 1920);
+        // Hybrid 20 ms, two frames of different size.
+        assert_eq!(opus_packet_samples(&[0x6A]), 1920);
+        // CELT 2.5 ms, frame count in the second byte.
+        assert_eq!(opus_packet_samples(&[0x83, 0x05]), 600);
+        assert_eq!(opus_packet_samples(&[0x83]), 0);
+    }
+}